@@ -0,0 +1,133 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use bsv::{ExtendedPublicKey, PublicKey, Transaction};
+use sigil_types::TypedSigning;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use twetch_sdk::{
+    CoinSelection, FeePolicy, MnemonicSigner, Signer, TxBuilder, TxBuilderOutput, Wallet, UTXO,
+};
+
+const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
+const INPUTS: usize = 50;
+const ROUNDS: u32 = 5;
+
+// What every build paid before the key cache: one mnemonic derivation per key access.
+struct UncachedSigner;
+
+impl UncachedSigner {
+    fn keys() -> MnemonicSigner {
+        MnemonicSigner::new(SEED.to_string())
+    }
+}
+
+#[async_trait]
+impl Signer for UncachedSigner {
+    fn account_public_key(&self) -> Result<PublicKey> {
+        UncachedSigner::keys().account_public_key()
+    }
+
+    fn wallet_xpub(&self) -> Result<ExtendedPublicKey> {
+        UncachedSigner::keys().wallet_xpub()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>> {
+        UncachedSigner::keys().sign_message(message).await
+    }
+
+    async fn sign_typed(&self, typed_signing: &TypedSigning) -> Result<TypedSigning> {
+        UncachedSigner::keys().sign_typed(typed_signing).await
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut Transaction,
+        utxos: &Vec<Option<UTXO>>,
+    ) -> Result<()> {
+        for i in 0..utxos.len() {
+            let mut only = vec![None; utxos.len()];
+            only[i] = utxos[i].clone();
+            UncachedSigner::keys().sign_transaction(tx, &only).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn multi_input_builder(wallet: &Wallet) -> TxBuilder {
+    let utxos = (0..INPUTS)
+        .map(|i| UTXO {
+            txid: format!("{:064x}", i + 1),
+            vout: 0,
+            satoshis: 10000,
+            path: i as i32,
+            script: None,
+            height: None,
+            script_type: None,
+        })
+        .collect();
+
+    TxBuilder {
+        outputs: vec![TxBuilderOutput {
+            sats: (INPUTS as u64 - 1) * 10000,
+            address: Some(wallet.account_address().unwrap().to_string().unwrap()),
+            to: None,
+            script: None,
+            args: None,
+            encrypt_args: None,
+        }],
+        auto_fund: true,
+        coin_selection: CoinSelection::LargestFirst,
+        fee_policy: FeePolicy::default(),
+        utxos: Some(utxos),
+        offline: true,
+        ..Default::default()
+    }
+}
+
+async fn build(wallet: &Wallet, builder: &TxBuilder) -> Duration {
+    let start = Instant::now();
+    let built = match wallet.build_tx(builder).await {
+        Ok(v) => v,
+        Err(e) => panic!("{} input build failed: {:?}", INPUTS, e),
+    };
+    let elapsed = start.elapsed();
+
+    assert!(built.signed);
+    assert_eq!(built.tx.get_ninputs(), INPUTS);
+    if let Some(id) = &built.reservation {
+        wallet.reservations().release(id).unwrap();
+    }
+
+    elapsed
+}
+
+#[tokio::main]
+async fn main() {
+    let uncached_wallet = Wallet::from_signer(Arc::new(UncachedSigner));
+    let builder = multi_input_builder(&uncached_wallet);
+
+    let mut uncached = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        uncached += build(&uncached_wallet, &builder).await;
+    }
+
+    let wallet = Wallet::new(SEED.to_string());
+    let first = build(&wallet, &builder).await;
+
+    let mut cached = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        cached += build(&wallet, &builder).await;
+    }
+
+    println!("{} input signed build", INPUTS);
+    println!("  uncached build          : {:?}", uncached / ROUNDS);
+    println!("  first build (cold cache): {:?}", first);
+    println!("  cached build            : {:?}", cached / ROUNDS);
+    println!(
+        "  speedup                 : {:.1}x",
+        uncached.as_secs_f64() / cached.as_secs_f64()
+    );
+}
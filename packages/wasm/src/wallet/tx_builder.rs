@@ -61,6 +61,11 @@ impl BuiltTx {
         self.0.fee_sats
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn fee_rate(&self) -> f64 {
+        self.0.fee_rate
+    }
+
     #[wasm_bindgen(getter)]
     pub fn num_payment_destinations(&self) -> usize {
         self.0.payment_destinations.len()
@@ -86,21 +91,30 @@ impl BuiltTx {
         let value = serde_json::to_value(&self.0.typed_signing).unwrap();
         JsValue::from_serde(&value).unwrap()
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn signed(&self) -> bool {
+        self.0.signed
+    }
+
+    pub async fn sign(built: BuiltTx, wallet: Wallet) -> Result<BuiltTx, JsValue> {
+        match wallet::TxBuilder::sign(&built.into(), &wallet.into()).await {
+            Ok(v) => Ok(v.into()),
+            Err(e) => {
+                let payload = json!({
+                    "message": format!("{:?}", e),
+                });
+                Err(JsValue::from_serde(&payload).unwrap())
+            }
+        }
+    }
 }
 
 #[wasm_bindgen]
 impl TxBuilder {
     #[wasm_bindgen(constructor)]
     pub fn new() -> TxBuilder {
-        TxBuilder(wallet::TxBuilder {
-            network: wallet::Networks::BSV,
-            contract: None,
-            outputs: vec![],
-            extended_tx: None,
-            change_address: None,
-            auto_fund: false,
-            typed_signing: None,
-        })
+        TxBuilder(wallet::TxBuilder::default())
     }
 
     pub fn from_json(value: JsValue) -> Result<TxBuilder, JsValue> {
@@ -1,7 +1,10 @@
 use crate::{EphemeralCipher, TypedSigning};
 use bsv_wasm::{ExtendedPrivateKey, ExtendedPublicKey, P2PKHAddress, PublicKey, Transaction};
-use twetch_sdk::{wallet, UTXO};
+use js_sys::Promise;
+use serde_json::json;
+use twetch_sdk::{wallet, SdkConfig, UTXO};
 use wasm_bindgen::{prelude::*, JsValue};
+use wasm_bindgen_futures::future_to_promise;
 
 #[wasm_bindgen]
 pub struct Wallet(wallet::Wallet);
@@ -25,6 +28,47 @@ impl Wallet {
         Wallet(wallet::Wallet::new(seed))
     }
 
+    pub fn from_mnemonic(phrase: String, passphrase: Option<String>) -> Result<Wallet, JsValue> {
+        match wallet::Wallet::from_mnemonic(&phrase, passphrase) {
+            Ok(v) => Ok(v.into()),
+            Err(e) => {
+                let payload = json!({
+                    "message": format!("{}", e),
+                });
+                Err(JsValue::from_serde(&payload).unwrap())
+            }
+        }
+    }
+
+    pub fn generate_mnemonic(word_count: usize) -> Option<String> {
+        match wallet::Wallet::generate_mnemonic(word_count) {
+            Ok(v) => Some(v),
+            Err(_) => None,
+        }
+    }
+
+    pub fn watch_only(
+        account_public_key: PublicKey,
+        wallet_xpub: Option<ExtendedPublicKey>,
+    ) -> Wallet {
+        Wallet(wallet::Wallet::watch_only(
+            account_public_key.into(),
+            wallet_xpub.map(|e| e.into()),
+        ))
+    }
+
+    /// Same keys talking to the endpoints of `config`, an `SdkConfig` object.
+    pub fn with_config(&self, config: JsValue) -> Result<Wallet, JsValue> {
+        match config.into_serde::<SdkConfig>() {
+            Ok(v) => Ok(Wallet(self.0.with_config(v))),
+            Err(e) => Err(JsValue::from_str(&e.to_string())),
+        }
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        self.0.is_watch_only()
+    }
+
     pub fn from_seed_and_token(seed: String, token: String) -> Option<Wallet> {
         match wallet::Wallet::from_seed_and_token(seed, token) {
             Ok(v) => Some(v.into()),
@@ -102,26 +146,68 @@ impl Wallet {
         }
     }
 
-    pub fn sign_typed(&self, typed_signing: TypedSigning) -> Option<TypedSigning> {
-        match wallet::Wallet::sign_typed(&self.0, &mut typed_signing.into()) {
-            Ok(v) => Some(v.into()),
-            Err(_) => None,
-        }
+    /// Resolves the signed `TypedSigning`, rejects with `{ message }`.
+    pub fn sign_typed(&self, typed_signing: TypedSigning) -> Promise {
+        let wallet = self.0.clone();
+        let typed_signing = typed_signing.into();
+
+        future_to_promise(async move {
+            match wallet.sign_typed(&typed_signing).await {
+                Ok(v) => Ok(TypedSigning::from(v).into()),
+                Err(e) => {
+                    let payload = json!({
+                        "message": format!("{}", e),
+                    });
+                    Err(JsValue::from_serde(&payload).unwrap())
+                }
+            }
+        })
     }
 
-    pub fn sign_message(&self, message: String) -> Option<String> {
-        match wallet::Wallet::sign_message(&self.0, message) {
-            Ok(v) => Some(v),
-            Err(_) => None,
-        }
+    /// Resolves the signature, rejects with `{ message }`.
+    pub fn sign_message(&self, message: String) -> Promise {
+        let wallet = self.0.clone();
+
+        future_to_promise(async move {
+            match wallet.sign_message(message).await {
+                Ok(v) => Ok(v.into()),
+                Err(e) => {
+                    let payload = json!({
+                        "message": format!("{}", e),
+                    });
+                    Err(JsValue::from_serde(&payload).unwrap())
+                }
+            }
+        })
     }
 
-    pub fn sign_transaction(&self, transaction: Transaction) -> Option<Transaction> {
+    /// Resolves the signed `Transaction`, rejects with `{ message }`.
+    pub fn sign_transaction(&self, transaction: Transaction) -> Promise {
+        let wallet = self.0.clone();
         let mut tx = transaction.into();
-        match wallet::Wallet::sign_transaction(&self.0, &mut tx, &Vec::new()) {
-            Ok(_) => Some(tx.into()),
-            Err(_) => None,
-        }
+
+        future_to_promise(async move {
+            match wallet.sign_transaction(&mut tx, &Vec::new()).await {
+                Ok(_) => Ok(Transaction::from(tx).into()),
+                Err(e) => {
+                    let payload = json!({
+                        "message": format!("{}", e),
+                    });
+                    Err(JsValue::from_serde(&payload).unwrap())
+                }
+            }
+        })
+    }
+
+    pub fn balance(&self, network: String) -> Promise {
+        let wallet = self.0.clone();
+
+        future_to_promise(async move {
+            match wallet.balance(&network.into()).await {
+                Ok(v) => Ok(JsValue::from_serde(&serde_json::to_value(v).unwrap()).unwrap()),
+                Err(_) => Ok(JsValue::undefined()),
+            }
+        })
     }
 
     pub async fn utxos(account_public_key: PublicKey, network: String) -> Result<JsValue, JsValue> {
@@ -1,4 +1,4 @@
-use crate::PayParams;
+use crate::{HttpTransport, PayParams, SdkConfig, TransportRequest};
 use anyhow::Result;
 use bsv::Transaction;
 use serde::{Deserialize, Serialize};
@@ -7,6 +7,8 @@ use serde_json::{json, Value};
 pub struct Api {
     pub url: String,
     pub token: String,
+    pub client_identifier: String,
+    transport: HttpTransport,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,12 +42,27 @@ pub struct PublishParams {
 
 impl Api {
     pub fn new(url: String, token: String) -> Api {
-        Api { url, token }
+        Api {
+            url,
+            token,
+            client_identifier: SdkConfig::current().client_identifier,
+            transport: HttpTransport::shared(),
+        }
     }
 
-    pub fn post(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client
+    pub fn with_transport(self, transport: HttpTransport) -> Api {
+        Api { transport, ..self }
+    }
+
+    pub fn with_client_identifier(self, client_identifier: String) -> Api {
+        Api {
+            client_identifier,
+            ..self
+        }
+    }
+
+    pub fn post(&self, path: String) -> TransportRequest {
+        self.transport
             .post(format!("{}{}", self.url, path))
             .header("Authorization", format!("Bearer {}", self.token))
     }
@@ -54,7 +71,7 @@ impl Api {
         let payload = json!({
             "args": args,
             "action": action,
-            "client_identifier": "1325c30a-7eb3-4169-a6f4-330eeeb8ca49",
+            "client_identifier": self.client_identifier,
             "payload": {
                 "resolveChange": true
             }
@@ -0,0 +1,243 @@
+use crate::api::broadcaster::broadcast_result;
+use crate::{
+    BroadcastResult, BroadcastStatus, Broadcaster, ExtendedFormat, HttpTransport, TransportRequest,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use bsv::Transaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Client for an ARC transaction processor, the successor of mAPI.
+pub struct ArcApi {
+    pub url: String,
+    pub token: Option<String>,
+    transport: HttpTransport,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ArcTxStatus {
+    Queued,
+    Received,
+    Stored,
+    AnnouncedToNetwork,
+    RequestedByNetwork,
+    SentToNetwork,
+    AcceptedByNetwork,
+    SeenInOrphanMempool,
+    SeenOnNetwork,
+    Mined,
+    Confirmed,
+    Rejected,
+    DoubleSpendAttempted,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Answer to a submit or status request. Errors such as a malformed or
+/// underpaying transaction come back with `status` and `detail` instead of
+/// `tx_status`, validation errors using statuses 460 to 469.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcTxResponse {
+    pub txid: Option<String>,
+    pub tx_status: Option<ArcTxStatus>,
+    pub status: Option<u16>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+    pub extra_info: Option<String>,
+    pub block_hash: Option<String>,
+    pub block_height: Option<u64>,
+    pub merkle_path: Option<String>,
+    pub competing_txs: Option<Vec<String>>,
+    pub timestamp: Option<String>,
+}
+
+impl ArcTxResponse {
+    fn reason(&self) -> String {
+        [&self.title, &self.detail, &self.extra_info]
+            .iter()
+            .filter_map(|e| e.as_ref().filter(|e| !e.is_empty()))
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    pub fn status(&self) -> BroadcastStatus {
+        match self.tx_status {
+            Some(ArcTxStatus::Rejected) => BroadcastStatus::from_error(&self.reason()),
+            Some(ArcTxStatus::DoubleSpendAttempted) => BroadcastStatus::Rejected(format!(
+                "double spend attempted, competing with {}",
+                self.competing_txs.clone().unwrap_or_default().join(", ")
+            )),
+            Some(ArcTxStatus::SeenInOrphanMempool) => {
+                BroadcastStatus::Transient("parents of the transaction are missing".to_string())
+            }
+            Some(ArcTxStatus::Unknown) => BroadcastStatus::Transient(self.reason()),
+            Some(_) => BroadcastStatus::Accepted,
+            // only ARC's own validation codes judge the transaction, auth
+            // errors, a wrong endpoint or a rate limit say nothing about it
+            None => match self.status {
+                Some(v) if (460..470).contains(&v) => BroadcastStatus::from_error(&self.reason()),
+                _ => BroadcastStatus::Transient(self.reason()),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ArcSubmitOptions {
+    /// ARC posts status updates of the transaction here.
+    pub callback_url: Option<String>,
+    /// Sent back as a bearer token with each callback.
+    pub callback_token: Option<String>,
+    /// Holds the response until the transaction reaches this status.
+    pub wait_for: Option<ArcTxStatus>,
+}
+
+impl ArcApi {
+    pub fn new(url: String, token: Option<String>) -> ArcApi {
+        ArcApi {
+            url,
+            token,
+            transport: HttpTransport::shared(),
+        }
+    }
+
+    pub fn with_transport(self, transport: HttpTransport) -> ArcApi {
+        ArcApi { transport, ..self }
+    }
+
+    fn authorize(&self, request: TransportRequest) -> TransportRequest {
+        match &self.token {
+            Some(v) => request.header("Authorization", format!("Bearer {}", v)),
+            None => request,
+        }
+    }
+
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.authorize(self.transport.get(format!("{}{}", self.url, path)))
+    }
+
+    pub fn post(&self, path: String, options: &ArcSubmitOptions) -> TransportRequest {
+        let mut request = self.authorize(self.transport.post(format!("{}{}", self.url, path)));
+
+        if let Some(v) = &options.callback_url {
+            request = request.header("X-CallbackUrl", v);
+        }
+
+        if let Some(v) = &options.callback_token {
+            request = request.header("X-CallbackToken", v);
+        }
+
+        if let Some(v) = options.wait_for {
+            if let Value::String(v) = serde_json::to_value(v).unwrap_or_default() {
+                request = request.header("X-WaitFor", v);
+            }
+        }
+
+        request
+    }
+
+    /// Submits a transaction in raw or Extended Format, see `ExtendedFormat`.
+    pub async fn submit_tx(
+        &self,
+        rawtx: &[u8],
+        options: &ArcSubmitOptions,
+    ) -> Result<ArcTxResponse> {
+        let res = self
+            .post("/v1/tx".to_string(), options)
+            .json(&json!({ "rawTx": hex::encode(rawtx) }))
+            .send()
+            .await?
+            .json::<ArcTxResponse>()
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Submits several transactions at once, parents before their children.
+    pub async fn submit_txs(
+        &self,
+        rawtxs: &[Vec<u8>],
+        options: &ArcSubmitOptions,
+    ) -> Result<Vec<ArcTxResponse>> {
+        let payload: Vec<Value> = rawtxs
+            .iter()
+            .map(|e| json!({ "rawTx": hex::encode(e) }))
+            .collect();
+
+        let res = self
+            .post("/v1/txs".to_string(), options)
+            .json(&payload)
+            .send()
+            .await?
+            .json::<Vec<ArcTxResponse>>()
+            .await?;
+
+        Ok(res)
+    }
+
+    pub async fn tx_status(&self, txid: &str) -> Result<ArcTxResponse> {
+        let res = self
+            .get(format!("/v1/tx/{}", txid))
+            .send()
+            .await?
+            .json::<ArcTxResponse>()
+            .await?;
+
+        Ok(res)
+    }
+}
+
+/// Submits through ARC in Extended Format when every input knows what it
+/// spends, in the raw format otherwise. Transactions signed by a wallet do,
+/// see `ExtendedFormat::from_tx`.
+pub struct ArcBroadcaster {
+    api: ArcApi,
+    options: ArcSubmitOptions,
+}
+
+impl ArcBroadcaster {
+    pub fn new(url: String, token: Option<String>) -> ArcBroadcaster {
+        ArcBroadcaster::from_api(ArcApi::new(url, token))
+    }
+
+    pub fn from_api(api: ArcApi) -> ArcBroadcaster {
+        ArcBroadcaster {
+            api,
+            options: ArcSubmitOptions::default(),
+        }
+    }
+
+    pub fn with_options(self, options: ArcSubmitOptions) -> ArcBroadcaster {
+        ArcBroadcaster { options, ..self }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Broadcaster for ArcBroadcaster {
+    fn name(&self) -> String {
+        format!("arc {}", self.api.url)
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> BroadcastResult {
+        let rawtx = match ExtendedFormat::from_tx(tx, &[]) {
+            Ok(v) => Ok(v),
+            Err(_) => tx.to_bytes().map_err(|e| e.into()),
+        };
+
+        let res = match rawtx {
+            Ok(v) => self
+                .api
+                .submit_tx(&v, &self.options)
+                .await
+                .map(|e| (e.status(), serde_json::to_value(&e).unwrap_or_default())),
+            Err(e) => Err(e),
+        };
+
+        broadcast_result(self.name(), tx, res)
+    }
+}
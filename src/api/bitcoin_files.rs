@@ -1,8 +1,10 @@
+use crate::{HttpTransport, TransportRequest};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 pub struct BitcoinFilesApi {
     url: String,
+    transport: HttpTransport,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -19,18 +21,24 @@ pub struct ContractInit {
 
 impl BitcoinFilesApi {
     pub fn new(url: String) -> BitcoinFilesApi {
-        BitcoinFilesApi { url }
+        BitcoinFilesApi {
+            url,
+            transport: HttpTransport::shared(),
+        }
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.get(format!("{}{}", self.url, path))
+    pub fn with_transport(self, transport: HttpTransport) -> BitcoinFilesApi {
+        BitcoinFilesApi { transport, ..self }
+    }
+
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport.get(format!("{}{}", self.url, path))
     }
 
     pub async fn contract(&self, txid: &String) -> Result<ContractInit> {
         let res = self
             .get(format!("/{}", txid))
-            .send()
+            .send_checked()
             .await?
             .json::<ContractInit>()
             .await?;
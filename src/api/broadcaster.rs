@@ -0,0 +1,299 @@
+use crate::{MapiApi, MetasyncApi, Networks, RPCClient, Wallet};
+use anyhow::Result;
+use async_trait::async_trait;
+use bsv::Transaction;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Errors meaning the transaction was already accepted, by us or someone else.
+pub const VALID_ERRORS: [&str; 5] = [
+    "ERROR: 257: txn-already-known",             // mapi
+    "257: txn-already-known",                    // node
+    "ERROR: Transaction already in the mempool", // mapi
+    "Transaction already known",                 // mapi
+    "Transaction already in the mempool",        // node and mapi
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BroadcastStatus {
+    Accepted,
+    AlreadyKnown,
+    /// The transaction is invalid or against the backend's policy.
+    Rejected(String),
+    /// The backend couldn't be reached or gave an unusable answer, trying
+    /// again or elsewhere may work.
+    Transient(String),
+}
+
+impl BroadcastStatus {
+    /// Status for an error returned by a node or miner.
+    pub fn from_error(message: &str) -> BroadcastStatus {
+        match VALID_ERRORS.iter().any(|e| message.contains(e)) {
+            true => BroadcastStatus::AlreadyKnown,
+            false => BroadcastStatus::Rejected(message.to_string()),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            BroadcastStatus::Accepted | BroadcastStatus::AlreadyKnown
+        )
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            BroadcastStatus::Accepted => 3,
+            BroadcastStatus::AlreadyKnown => 2,
+            BroadcastStatus::Rejected(_) => 1,
+            BroadcastStatus::Transient(_) => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BroadcastResult {
+    /// `Broadcaster::name` of the backend that answered.
+    pub broadcaster: String,
+    pub txid: Option<String>,
+    pub status: BroadcastStatus,
+    /// What the backend sent back, as is.
+    pub response: Value,
+}
+
+impl BroadcastResult {
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Broadcaster: Send + Sync {
+    fn name(&self) -> String;
+
+    async fn broadcast(&self, tx: &Transaction) -> BroadcastResult;
+}
+
+fn to_value<T: Serialize>(response: &T) -> Value {
+    serde_json::to_value(response).unwrap_or_default()
+}
+
+pub(crate) fn broadcast_result(
+    name: String,
+    tx: &Transaction,
+    res: Result<(BroadcastStatus, Value)>,
+) -> BroadcastResult {
+    let (status, response) = match res {
+        Ok(v) => v,
+        Err(e) => (BroadcastStatus::Transient(e.to_string()), Value::Null),
+    };
+
+    BroadcastResult {
+        broadcaster: name,
+        txid: tx.get_id_hex().ok(),
+        status,
+        response,
+    }
+}
+
+pub struct MapiBroadcaster {
+    api: MapiApi,
+}
+
+impl MapiBroadcaster {
+    pub fn new(url: String, token: String) -> MapiBroadcaster {
+        MapiBroadcaster {
+            api: MapiApi::new(url, token),
+        }
+    }
+
+    pub fn from_api(api: MapiApi) -> MapiBroadcaster {
+        MapiBroadcaster { api }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Broadcaster for MapiBroadcaster {
+    fn name(&self) -> String {
+        format!("mapi {}", self.api.url)
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> BroadcastResult {
+        let res = match tx.to_bytes() {
+            Ok(v) => self
+                .api
+                .submit_rawtx(&v)
+                .await
+                .map(|e| (e.status(), to_value(&e))),
+            Err(e) => Err(e.into()),
+        };
+
+        broadcast_result(self.name(), tx, res)
+    }
+}
+
+pub struct RpcBroadcaster {
+    client: RPCClient,
+}
+
+impl RpcBroadcaster {
+    pub fn new(client: RPCClient) -> RpcBroadcaster {
+        RpcBroadcaster { client }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Broadcaster for RpcBroadcaster {
+    fn name(&self) -> String {
+        format!("rpc {}", self.client.host)
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> BroadcastResult {
+        let res = match tx.to_bytes() {
+            Ok(v) => self
+                .client
+                .send_rawtransaction(&v)
+                .await
+                .map(|e| (e.status(), to_value(&e))),
+            Err(e) => Err(e.into()),
+        };
+
+        broadcast_result(self.name(), tx, res)
+    }
+}
+
+/// Broadcasts through metasync on behalf of a twetch user.
+pub struct MetasyncBroadcaster {
+    url: String,
+    api: MetasyncApi,
+    network: Networks,
+    user_id: String,
+}
+
+impl MetasyncBroadcaster {
+    pub fn new(url: String, network: Networks, user_id: String) -> MetasyncBroadcaster {
+        MetasyncBroadcaster {
+            api: MetasyncApi::new(url.clone()),
+            url,
+            network,
+            user_id,
+        }
+    }
+
+    pub fn from_wallet(wallet: &Wallet, network: Networks) -> Result<MetasyncBroadcaster> {
+        match &wallet.user_id {
+            Some(v) => Ok(MetasyncBroadcaster::new(
+                wallet.config().metasync_url.clone(),
+                network,
+                v.clone(),
+            )),
+            None => anyhow::bail!("MetasyncAPI Error: no user found in wallet"),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Broadcaster for MetasyncBroadcaster {
+    fn name(&self) -> String {
+        format!("metasync {}", self.url)
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> BroadcastResult {
+        let res = self
+            .api
+            .broadcast_as(tx, &self.network, &self.user_id)
+            .await
+            .map(|e| (e.status(), to_value(&e)));
+
+        broadcast_result(self.name(), tx, res)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BroadcastMode {
+    /// Tries each broadcaster in order until one gives a definite answer.
+    /// Only transient failures move on to the next one, a transaction one
+    /// backend rejects is invalid everywhere.
+    Failover,
+    /// Sends the transaction to every broadcaster at once.
+    Multicast,
+}
+
+/// Broadcasts through several backends and answers with the best result, so
+/// one accepting or already knowing the transaction is a success.
+pub struct MultiBroadcaster {
+    broadcasters: Vec<Arc<dyn Broadcaster>>,
+    mode: BroadcastMode,
+}
+
+impl MultiBroadcaster {
+    pub fn new(broadcasters: Vec<Arc<dyn Broadcaster>>, mode: BroadcastMode) -> MultiBroadcaster {
+        MultiBroadcaster { broadcasters, mode }
+    }
+
+    pub fn failover(broadcasters: Vec<Arc<dyn Broadcaster>>) -> MultiBroadcaster {
+        MultiBroadcaster::new(broadcasters, BroadcastMode::Failover)
+    }
+
+    pub fn multicast(broadcasters: Vec<Arc<dyn Broadcaster>>) -> MultiBroadcaster {
+        MultiBroadcaster::new(broadcasters, BroadcastMode::Multicast)
+    }
+
+    /// Result of every broadcaster that was asked, in order.
+    pub async fn broadcast_all(&self, tx: &Transaction) -> Vec<BroadcastResult> {
+        if self.mode == BroadcastMode::Multicast {
+            return join_all(self.broadcasters.iter().map(|e| e.broadcast(tx))).await;
+        }
+
+        let mut results = Vec::new();
+        for broadcaster in &self.broadcasters {
+            let res = broadcaster.broadcast(tx).await;
+            let done = !matches!(res.status, BroadcastStatus::Transient(_));
+            results.push(res);
+
+            if done {
+                break;
+            }
+        }
+
+        results
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Broadcaster for MultiBroadcaster {
+    fn name(&self) -> String {
+        let names: Vec<String> = self.broadcasters.iter().map(|e| e.name()).collect();
+        format!("{:?} [{}]", self.mode, names.join(", "))
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> BroadcastResult {
+        let mut best: Option<BroadcastResult> = None;
+
+        for res in self.broadcast_all(tx).await {
+            best = match best {
+                Some(v) if v.status.rank() >= res.status.rank() => Some(v),
+                _ => Some(res),
+            };
+        }
+
+        match best {
+            Some(v) => v,
+            None => broadcast_result(
+                self.name(),
+                tx,
+                Ok((
+                    BroadcastStatus::Transient("no broadcasters".to_string()),
+                    Value::Null,
+                )),
+            ),
+        }
+    }
+}
@@ -1,20 +1,29 @@
+use crate::{HttpTransport, TransportRequest, TwetchError};
 use anyhow::Result;
 use serde_json::{json, Value};
 
 pub struct GraphqlApi {
     pub url: String,
     pub token: String,
+    transport: HttpTransport,
 }
 
 impl GraphqlApi {
     pub fn new(url: String, token: String) -> GraphqlApi {
-        GraphqlApi { url, token }
+        GraphqlApi {
+            url,
+            token,
+            transport: HttpTransport::shared(),
+        }
     }
 
-    pub fn client(&self) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client
-            .post(&self.url)
+    pub fn with_transport(self, transport: HttpTransport) -> GraphqlApi {
+        GraphqlApi { transport, ..self }
+    }
+
+    pub fn client(&self) -> TransportRequest {
+        self.transport
+            .post(self.url.clone())
             .header("Authorization", format!("Bearer {}", self.token))
     }
 
@@ -33,9 +42,25 @@ impl GraphqlApi {
             .json::<Value>()
             .await?;
 
-        let data = res.get("data").unwrap().clone();
+        let errors: Vec<String> = match res.get("errors").and_then(|e| e.as_array()) {
+            Some(v) => v
+                .iter()
+                .map(|e| match e.get("message").and_then(|e| e.as_str()) {
+                    Some(v) => v.to_string(),
+                    None => e.to_string(),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        if !errors.is_empty() {
+            return Err(TwetchError::api("graphql", errors).into());
+        }
 
-        Ok(data)
+        match res.get("data") {
+            Some(v) if !v.is_null() => Ok(v.clone()),
+            _ => Err(missing("data").into()),
+        }
     }
 
     pub async fn create_message(&self, payload: Value) -> Result<Value> {
@@ -47,8 +72,8 @@ impl GraphqlApi {
             .graphql(query, Some(payload))
             .await?
             .get("createMessage")
-            .unwrap()
-            .clone();
+            .cloned()
+            .ok_or_else(|| missing("createMessage"))?;
         Ok(res)
     }
 
@@ -64,8 +89,8 @@ impl GraphqlApi {
             )
             .await?
             .get("createConversation")
-            .unwrap()
-            .clone();
+            .cloned()
+            .ok_or_else(|| missing("createConversation"))?;
         Ok(res)
     }
 
@@ -98,10 +123,13 @@ impl GraphqlApi {
             .graphql(query, None)
             .await?
             .get("allUsers")
-            .unwrap()
-            .get("nodes")
-            .unwrap()
-            .clone();
+            .and_then(|e| e.get("nodes"))
+            .cloned()
+            .ok_or_else(|| missing("allUsers"))?;
         Ok(res)
     }
 }
+
+fn missing(field: &str) -> TwetchError {
+    TwetchError::api("graphql", vec![format!("response has no {}", field)])
+}
@@ -1,108 +1,316 @@
 use crate::api::rpc::BroadcastResponse;
+use crate::{BroadcastStatus, HttpTransport, TransportRequest, TwetchError};
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use bsv::{PublicKey, Signature, SigningHash, ECDSA};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
 
 pub struct MapiApi {
     pub url: String,
     pub token: String,
+    /// Public key the miner signs its responses with, responses signed by
+    /// another key or unsigned are refused when set.
+    pub miner_id: Option<String>,
+    transport: HttpTransport,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct BroadcastMapiResponse {
+/// JSON envelope around every mAPI payload, signed by the miner.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiEnvelope {
     pub payload: Option<String>,
+    pub signature: Option<String>,
+    pub public_key: Option<String>,
+    pub encoding: Option<String>,
+    pub mimetype: Option<String>,
+    /// Set instead of the payload when the request failed.
     pub message: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct BroadcastMapiResponsePayload {
+pub type BroadcastMapiResponse = MapiEnvelope;
+
+impl MapiEnvelope {
+    pub fn is_signed(&self) -> bool {
+        self.signature.is_some() && self.public_key.is_some()
+    }
+
+    /// Checks the signature over the payload against the envelope's public key.
+    /// Unsigned envelopes pass, and a valid signature only proves the payload
+    /// matches whatever key came with it. Pin the key with `MapiApi.miner_id`
+    /// to know the miner signed it.
+    pub fn verify(&self) -> Result<()> {
+        let (signature, public_key) = match (&self.signature, &self.public_key) {
+            (Some(s), Some(k)) => (s, k),
+            (None, None) => return Ok(()),
+            _ => {
+                return Err(mapi_error(
+                    "envelope has a signature or public key but not both",
+                ))
+            }
+        };
+
+        let payload = match &self.payload {
+            Some(v) => v,
+            None => return Err(mapi_error("signed envelope has no payload")),
+        };
+
+        let valid = ECDSA::verify_digest(
+            payload.as_bytes(),
+            &PublicKey::from_hex(public_key)?,
+            &Signature::from_hex_der(signature)?,
+            SigningHash::Sha256,
+        )?;
+
+        match valid {
+            true => Ok(()),
+            false => Err(mapi_error("envelope signature is invalid")),
+        }
+    }
+
+    pub fn payload<T: DeserializeOwned>(&self) -> Result<T> {
+        match &self.payload {
+            Some(v) => Ok(serde_json::from_str(v)?),
+            None => Err(mapi_error(&self.message.clone().unwrap_or_default())),
+        }
+    }
+
+    pub fn status(&self) -> BroadcastStatus {
+        match self.payload::<MapiSubmitResult>() {
+            Ok(v) => v.status(),
+            Err(e) => BroadcastStatus::Transient(e.to_string()),
+        }
+    }
+}
+
+fn mapi_error(message: &str) -> anyhow::Error {
+    TwetchError::api("mapi", vec![message.to_string()]).into()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiSubmitResult {
+    pub api_version: Option<String>,
+    pub timestamp: Option<String>,
+    pub txid: String,
+    pub return_result: String,
+    pub result_description: String,
+    pub miner_id: Option<String>,
+    pub current_highest_block_hash: Option<String>,
+    pub current_highest_block_height: Option<u64>,
+    pub tx_second_mempool_expiry: Option<u64>,
+    #[serde(default)]
+    pub conflicted_with: Vec<Value>,
+}
+
+impl MapiSubmitResult {
+    pub fn status(&self) -> BroadcastStatus {
+        match self.return_result.as_str() {
+            "success" => BroadcastStatus::Accepted,
+            _ => BroadcastStatus::from_error(&self.result_description),
+        }
+    }
+}
+
+/// Merkle proof in the TSC format.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiMerkleProof {
+    pub index: u64,
+    pub tx_or_id: String,
+    pub target: String,
+    pub nodes: Vec<String>,
+    pub target_type: Option<String>,
+    pub proof_type: Option<String>,
+    #[serde(default)]
+    pub composite: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiTxStatus {
+    pub api_version: Option<String>,
+    pub timestamp: Option<String>,
     pub txid: String,
-    pub returnResult: String,
-    pub resultDescription: String,
+    pub return_result: String,
+    pub result_description: String,
+    pub block_hash: Option<String>,
+    pub block_height: Option<u64>,
+    pub confirmations: Option<u64>,
+    pub miner_id: Option<String>,
+    pub tx_second_mempool_expiry: Option<u64>,
+    pub merkle_proof: Option<MapiMerkleProof>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiSubmitOptions {
+    /// The miner posts merkle proofs and double spend notifications here.
+    pub callback_url: Option<String>,
+    pub callback_token: Option<String>,
+    /// Asks for a merkle proof callback once the transaction is mined.
+    pub merkle_proof: bool,
+    /// `TSC` for the standard format.
+    pub merkle_format: Option<String>,
+    pub ds_check: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapiFeeAmount {
+    pub satoshis: u64,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiFee {
+    pub fee_type: String,
+    pub mining_fee: MapiFeeAmount,
+    pub relay_fee: MapiFeeAmount,
 }
 
-const VALID_ERRORS: [&str; 5] = [
-    "ERROR: 257: txn-already-known",             // mapi
-    "257: txn-already-known",                    // node
-    "ERROR: Transaction already in the mempool", // mapi
-    "Transaction already known",                 // mapi
-    "Transaction already in the mempool",        // node and mapi
-];
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MapiFeeQuote {
+    pub api_version: Option<String>,
+    pub timestamp: Option<String>,
+    pub expiry_time: Option<String>,
+    pub miner_id: Option<String>,
+    pub current_highest_block_hash: Option<String>,
+    pub current_highest_block_height: Option<u64>,
+    pub fees: Vec<MapiFee>,
+}
+
+impl MapiFeeQuote {
+    /// Mining fee in sats per byte for a fee type, `standard` or `data`.
+    pub fn rate(&self, fee_type: &str) -> Option<f64> {
+        self.fees
+            .iter()
+            .find(|e| e.fee_type == fee_type && e.mining_fee.bytes > 0)
+            .map(|e| e.mining_fee.satoshis as f64 / e.mining_fee.bytes as f64)
+    }
+}
 
 impl MapiApi {
     pub fn new(url: String, token: String) -> MapiApi {
-        MapiApi { url, token }
+        MapiApi {
+            url,
+            token,
+            miner_id: None,
+            transport: HttpTransport::shared(),
+        }
+    }
+
+    pub fn with_transport(self, transport: HttpTransport) -> MapiApi {
+        MapiApi { transport, ..self }
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client
+    pub fn with_miner_id(self, miner_id: String) -> MapiApi {
+        MapiApi {
+            miner_id: Some(miner_id),
+            ..self
+        }
+    }
+
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport
             .get(format!("{}{}", self.url, path))
             .header("Authorization", format!("Bearer {}", self.token))
     }
 
-    pub fn post(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client
+    pub fn post(&self, path: String) -> TransportRequest {
+        self.transport
             .post(format!("{}{}", self.url, path))
             .header("Authorization", format!("Bearer {}", self.token))
     }
 
-    pub async fn broadcast_rawtx(&self, rawtx: &Vec<u8>) -> Result<BroadcastResponse> {
-        let res = self
-            .post("/tx".to_string())
-            .header("Content-Type", "application/octet-stream")
-            .body(rawtx.clone())
-            .send()
-            .await?
-            .json::<BroadcastMapiResponse>()
-            .await?;
-
-        let response_payload = match &res.payload {
-            Some(v) => v,
-            None => {
-                return Ok(BroadcastResponse {
-                    success: false,
-                    response: serde_json::to_value(res)?,
-                })
+    /// Verifies the envelope, and that `miner_id` signed it when set.
+    pub fn verify(&self, envelope: &MapiEnvelope) -> Result<()> {
+        if let Some(miner_id) = &self.miner_id {
+            if envelope.public_key.as_ref() != Some(miner_id) || envelope.signature.is_none() {
+                return Err(mapi_error("envelope is not signed by the miner"));
             }
-        };
+        }
+
+        envelope.verify()
+    }
 
-        let payload: BroadcastMapiResponsePayload = match serde_json::from_str(response_payload) {
-            Ok(v) => v,
-            Err(_) => {
-                return Ok(BroadcastResponse {
-                    success: false,
-                    response: serde_json::to_value(res)?,
-                })
+    /// Errors without an envelope, such as a 401 or a 502 from a proxy, come
+    /// back as a `TwetchError::Http` with the status.
+    async fn envelope(&self, request: TransportRequest) -> Result<MapiEnvelope> {
+        let res = request.send().await?;
+        let status = res.status();
+        let url = res.url().to_string();
+        let body = res.text().await?;
+
+        let envelope = serde_json::from_str::<MapiEnvelope>(&body)
+            .ok()
+            .filter(|e| e.payload.is_some() || e.message.is_some());
+
+        let res = match (envelope, status.is_success()) {
+            (Some(v), _) => v,
+            (None, true) => serde_json::from_str::<MapiEnvelope>(&body)?,
+            (None, false) => {
+                return Err(TwetchError::Http {
+                    url: Some(url),
+                    status: Some(status.as_u16()),
+                    message: match body.is_empty() {
+                        true => status.canonical_reason().unwrap_or_default().to_string(),
+                        false => body,
+                    },
+                }
+                .into())
             }
         };
 
-        if payload.returnResult == "success".to_string() {
-            return Ok(BroadcastResponse {
-                success: true,
-                response: serde_json::to_value(res)?,
-            });
-        }
+        self.verify(&res)?;
+        Ok(res)
+    }
 
-        let is_valid_error = match VALID_ERRORS
-            .iter()
-            .find(|e| e.to_string() == payload.resultDescription)
-        {
-            Some(_) => true,
-            None => false,
+    pub async fn fee_quote(&self) -> Result<MapiFeeQuote> {
+        self.envelope(self.get("/feeQuote".to_string()))
+            .await?
+            .payload()
+    }
+
+    /// Status of a transaction, with its merkle proof once mined when `merkle_proof` is set.
+    pub async fn tx_status(&self, txid: &str, merkle_proof: bool) -> Result<MapiTxStatus> {
+        let path = match merkle_proof {
+            true => format!("/tx/{}?merkleProof=true&merkleFormat=TSC", txid),
+            false => format!("/tx/{}", txid),
         };
 
-        if payload.returnResult == "failure" && is_valid_error {
-            return Ok(BroadcastResponse {
-                success: true,
-                response: serde_json::to_value(res)?,
-            });
-        }
+        self.envelope(self.get(path)).await?.payload()
+    }
+
+    pub async fn submit_rawtx(&self, rawtx: &Vec<u8>) -> Result<MapiEnvelope> {
+        self.envelope(
+            self.post("/tx".to_string())
+                .header("Content-Type", "application/octet-stream")
+                .body(rawtx.clone()),
+        )
+        .await
+    }
+
+    /// Submits with callbacks for merkle proofs and double spends.
+    pub async fn submit_tx(
+        &self,
+        rawtx: &Vec<u8>,
+        options: &MapiSubmitOptions,
+    ) -> Result<MapiSubmitResult> {
+        let mut payload = serde_json::to_value(options)?;
+        payload["rawtx"] = json!(hex::encode(rawtx));
+
+        self.envelope(self.post("/tx".to_string()).json(&payload))
+            .await?
+            .payload()
+    }
+
+    pub async fn broadcast_rawtx(&self, rawtx: &Vec<u8>) -> Result<BroadcastResponse> {
+        let res = self.submit_rawtx(rawtx).await?;
 
-        return Ok(BroadcastResponse {
-            success: false,
+        Ok(BroadcastResponse {
+            success: res.status().is_success(),
             response: serde_json::to_value(res)?,
-        });
+        })
     }
 }
@@ -1,4 +1,4 @@
-use crate::{Networks, Wallet};
+use crate::{BroadcastStatus, HttpTransport, Networks, TransportRequest, TwetchError, Wallet};
 use anyhow::Result;
 use bsv::{PublicKey, Transaction};
 use serde::{Deserialize, Serialize};
@@ -29,6 +29,8 @@ pub struct MetasyncUTXOResponse {
 #[derive(Serialize, Deserialize)]
 pub struct MetasyncApi {
     url: String,
+    #[serde(skip)]
+    transport: HttpTransport,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -48,9 +50,26 @@ pub struct Broadcast {
     pub error: Option<String>,
 }
 
+impl Broadcast {
+    pub fn status(&self) -> BroadcastStatus {
+        match (&self.txid, &self.error) {
+            (_, Some(e)) => BroadcastStatus::from_error(e),
+            (Some(_), None) => BroadcastStatus::Accepted,
+            (None, None) => BroadcastStatus::Transient("metasync returned no txid".to_string()),
+        }
+    }
+}
+
 impl MetasyncApi {
     pub fn new(url: String) -> MetasyncApi {
-        MetasyncApi { url }
+        MetasyncApi {
+            url,
+            transport: HttpTransport::shared(),
+        }
+    }
+
+    pub fn with_transport(self, transport: HttpTransport) -> MetasyncApi {
+        MetasyncApi { transport, ..self }
     }
 
     pub fn network(network: &Networks) -> String {
@@ -60,14 +79,12 @@ impl MetasyncApi {
         }
     }
 
-    pub fn post(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.post(format!("{}{}", self.url, path))
+    pub fn post(&self, path: String) -> TransportRequest {
+        self.transport.post(format!("{}{}", self.url, path))
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.get(format!("{}{}", self.url, path))
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport.get(format!("{}{}", self.url, path))
     }
 
     pub async fn submit_sigil_v3(
@@ -92,7 +109,7 @@ impl MetasyncApi {
     pub async fn uto(&self, outpoint: &String) -> Result<MetasyncUTO> {
         let res = self
             .get(format!("/uto/{}", outpoint))
-            .send()
+            .send_checked()
             .await?
             .json::<MetasyncUTO>()
             .await?;
@@ -113,8 +130,9 @@ impl MetasyncApi {
 
         let res = self
             .post("/wallet/utxo".to_string())
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<MetasyncUTXOResponse>()
             .await?;
@@ -137,7 +155,7 @@ impl MetasyncApi {
         let res = self
             .post(format!("/paymail/p2p-payment-destination/{}", paymail))
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<MetasyncPaymentDestination>()
             .await?;
@@ -150,15 +168,23 @@ impl MetasyncApi {
         network: &Networks,
         wallet: &Wallet,
     ) -> Result<Broadcast> {
-        anyhow::ensure!(
-            None != wallet.user_id,
-            "MetasyncAPI Error: no user found in wallet"
-        );
+        match &wallet.user_id {
+            Some(v) => self.broadcast_as(tx, network, v).await,
+            None => Err(TwetchError::Auth("no user found in wallet".to_string()).into()),
+        }
+    }
 
+    /// Broadcasts with `user_id` as the sender.
+    pub async fn broadcast_as(
+        &self,
+        tx: &Transaction,
+        network: &Networks,
+        user_id: &str,
+    ) -> Result<Broadcast> {
         let payload = json!({
             "hex": tx.to_hex()?,
             "network": MetasyncApi::network(network),
-            "metadata": { "sender": format!("{}@twetch.me", &wallet.user_id.clone().unwrap())  }
+            "metadata": { "sender": format!("{}@twetch.me", user_id) }
         });
         let res = self
             .post("/tx".to_string())
@@ -30,3 +30,12 @@ pub use rpc::*;
 
 pub mod mapi;
 pub use mapi::*;
+
+pub mod transport;
+pub use transport::*;
+
+pub mod broadcaster;
+pub use broadcaster::*;
+
+pub mod arc;
+pub use arc::*;
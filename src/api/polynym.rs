@@ -1,4 +1,4 @@
-use crate::{PaymentDestination, Wallet};
+use crate::{HttpTransport, PaymentDestination, TransportRequest, TwetchError, Wallet};
 use anyhow::Result;
 use bsv::{P2PKHAddress, Transaction};
 use serde::{Deserialize, Serialize};
@@ -6,6 +6,7 @@ use serde_json::json;
 
 pub struct PolynymApi {
     url: String,
+    transport: HttpTransport,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -42,23 +43,28 @@ pub struct PaymailP2PPaymentDestination {
 
 impl PolynymApi {
     pub fn new(url: String) -> PolynymApi {
-        PolynymApi { url }
+        PolynymApi {
+            url,
+            transport: HttpTransport::shared(),
+        }
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.get(format!("{}{}", self.url, path))
+    pub fn with_transport(self, transport: HttpTransport) -> PolynymApi {
+        PolynymApi { transport, ..self }
     }
 
-    pub fn post(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.post(format!("{}{}", self.url, path))
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport.get(format!("{}{}", self.url, path))
+    }
+
+    pub fn post(&self, path: String) -> TransportRequest {
+        self.transport.post(format!("{}{}", self.url, path))
     }
 
     pub async fn get_address(&self, paymail: &String) -> Result<P2PKHAddress> {
         let res = self
             .get(format!("/getAddress/{}", paymail))
-            .send()
+            .send_checked()
             .await?
             .json::<GetAddress>()
             .await?;
@@ -68,7 +74,7 @@ impl PolynymApi {
     pub async fn capabilities(&self, paymail: &String) -> Result<PaymailCapabilities> {
         let res = self
             .get(format!("/capabilities/{}", paymail))
-            .send()
+            .send_checked()
             .await?
             .json::<PaymailCapabilities>()
             .await?;
@@ -85,7 +91,7 @@ impl PolynymApi {
         let res = self
             .post(format!("/p2p/destination/{}", paymail))
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<PaymailP2PPaymentDestination>()
             .await?;
@@ -99,19 +105,19 @@ impl PolynymApi {
         payment_destination: &PaymentDestination,
         wallet: &Wallet,
     ) -> Result<()> {
-        anyhow::ensure!(
-            None != wallet.user_id,
-            "PolynymAPI Error: no user found in wallet"
-        );
+        let user_id = match &wallet.user_id {
+            Some(v) => v,
+            None => return Err(TwetchError::Auth("no user found in wallet".to_string()).into()),
+        };
 
         let payload = json!({ "hex": tx.to_hex()?, "reference": payment_destination.reference, "metadata": {
-            "sender": format!("{}@twetch.me", &wallet.user_id.clone().unwrap()),
+            "sender": format!("{}@twetch.me", user_id),
             "ref": payment_destination.reference,
         } });
 
         self.post(format!("/p2p/{}", payment_destination.paymail))
             .json(&payload)
-            .send()
+            .send_checked()
             .await?;
 
         Ok(())
@@ -1,6 +1,11 @@
+use crate::{BroadcastStatus, HttpTransport, TransportRequest, TwetchError};
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 #[derive(Deserialize, Default, Debug, Clone)]
 pub struct RPCClient {
@@ -8,16 +13,40 @@ pub struct RPCClient {
     pub port: Option<String>,
     pub user: String,
     pub password: String,
+    #[serde(skip)]
+    transport: HttpTransport,
+    /// Id of the last request, shared between clones.
+    #[serde(skip)]
+    last_id: Arc<AtomicU64>,
 }
 
-#[derive(Deserialize, Default, Debug)]
-pub struct RPCBlockchainInfoResponse {
-    pub result: Option<RPCBlockchainInfo>,
-    pub id: String,
+/// JSON-RPC response, `result` is `None` when the node answered with `error`.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RPCResponse<T> {
+    pub result: Option<T>,
+    #[serde(default)]
+    pub id: Value,
     pub error: Option<RPCError>,
 }
 
-#[derive(Deserialize, Default, Debug)]
+impl<T> RPCResponse<T> {
+    /// The result, or the node's error as a `TwetchError::Api`.
+    pub fn into_result(self) -> Result<T> {
+        match (self.result, self.error) {
+            (_, Some(e)) => Err(TwetchError::api("rpc", vec![e.to_string()]).into()),
+            (Some(v), None) => Ok(v),
+            (None, None) => Err(TwetchError::api("rpc", vec!["empty result".to_string()]).into()),
+        }
+    }
+}
+
+pub type RPCBlockchainInfoResponse = RPCResponse<RPCBlockchainInfo>;
+pub type RPCBlockHashResponse = RPCResponse<String>;
+pub type RPCBlockResponse = RPCResponse<RPCBlock>;
+pub type RPCRawBlockResponse = RPCResponse<String>;
+pub type RPCBroadcastResponse = RPCResponse<String>;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct RPCBlockchainInfo {
     pub blocks: u32,
     pub chain: String,
@@ -29,31 +58,31 @@ pub struct RPCBlockchainInfo {
     pub chainwork: String,
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct RPCError {
     pub code: Option<i32>,
     pub message: Option<String>,
 }
 
-#[derive(Deserialize, Default)]
-pub struct RPCBlockHashResponse {
-    pub result: Option<String>,
-    pub id: String,
-    pub error: Option<RPCError>,
-}
-
-#[derive(Deserialize, Default, Debug)]
-pub struct RPCBlockResponse {
-    pub result: Option<RPCBlock>,
-    pub id: String,
-    pub error: Option<RPCError>,
+impl std::fmt::Display for RPCError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.code.unwrap_or_default(),
+            self.message.clone().unwrap_or_default()
+        )
+    }
 }
 
-#[derive(Deserialize, Default, Debug)]
-pub struct RPCRawBlockResponse {
-    pub result: Option<String>,
-    pub id: String,
-    pub error: Option<RPCError>,
+impl RPCBroadcastResponse {
+    pub fn status(&self) -> BroadcastStatus {
+        match (&self.result, &self.error) {
+            (_, Some(e)) => BroadcastStatus::from_error(&e.message.clone().unwrap_or_default()),
+            (Some(_), None) => BroadcastStatus::Accepted,
+            (None, None) => BroadcastStatus::Transient("node returned no txid".to_string()),
+        }
+    }
 }
 
 #[derive(Deserialize, Default, Debug)]
@@ -62,110 +91,293 @@ pub struct BroadcastResponse {
     pub response: Value,
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct RPCBlock {
     pub tx: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCScript {
+    pub asm: String,
+    pub hex: String,
+    #[serde(rename = "type")]
+    pub script_type: Option<String>,
+    pub addresses: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCVin {
+    pub txid: Option<String>,
+    pub vout: Option<u32>,
+    pub coinbase: Option<String>,
+    #[serde(rename = "scriptSig")]
+    pub script_sig: Option<RPCScript>,
+    pub sequence: u32,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCVout {
+    /// In BSV, not satoshis.
+    pub value: f64,
+    pub n: u32,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: RPCScript,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCRawTransaction {
+    pub txid: String,
+    pub hash: Option<String>,
+    pub version: u32,
+    pub size: u64,
+    pub locktime: u32,
+    pub vin: Vec<RPCVin>,
+    pub vout: Vec<RPCVout>,
+    pub hex: String,
+    pub blockhash: Option<String>,
+    pub confirmations: Option<u64>,
+    pub time: Option<u64>,
+    pub blocktime: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCMempoolEntry {
+    pub size: u64,
+    pub fee: Option<f64>,
+    pub modifiedfee: Option<f64>,
+    pub time: u64,
+    pub height: u64,
+    #[serde(default)]
+    pub depends: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCTxOut {
+    pub bestblock: String,
+    pub confirmations: u64,
+    /// In BSV, not satoshis.
+    pub value: f64,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: RPCScript,
+    pub coinbase: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCMempoolAccept {
+    pub txid: String,
+    pub allowed: bool,
+    #[serde(rename = "reject-reason")]
+    pub reject_reason: Option<String>,
+}
+
+/// Output spent by the input being verified, for when the node doesn't have it.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCVerifyScriptPrevout {
+    pub txid: String,
+    pub vout: u32,
+    pub script_pub_key: String,
+    /// In BSV, not satoshis.
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCVerifyScript {
+    /// Hex of the spending transaction.
+    pub tx: String,
+    /// Input to verify.
+    pub n: u32,
+    pub prevout: Option<RPCVerifyScriptPrevout>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RPCVerifyScriptResult {
+    /// `ok` when the script verified.
+    pub result: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCMerkleProof {
+    pub flags: Option<u32>,
+    pub index: u64,
+    pub tx_or_id: String,
+    /// Block hash, header or merkle root depending on the flags.
+    pub target: Value,
+    pub nodes: Vec<String>,
+}
+
+/// One request of a batch, see `RPCClient::batch`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RPCCall {
+    pub method: String,
+    pub params: Value,
+}
+
+impl RPCCall {
+    pub fn new(method: &str, params: Value) -> RPCCall {
+        RPCCall {
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
 impl RPCClient {
-    pub fn client(&self) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
+    pub fn with_transport(self, transport: HttpTransport) -> RPCClient {
+        RPCClient { transport, ..self }
+    }
 
+    /// Nodes answer failed calls with a 500 and the reason in `error`, which
+    /// is the answer rather than a reason to retry.
+    pub fn client(&self) -> TransportRequest {
         let url = match &self.port {
             Some(port) => format!("{}:{}", self.host, port),
             None => format!("{}", self.host),
         };
 
-        client
+        self.transport
             .post(url)
             .basic_auth(&self.user, Some(&self.password))
+            .final_on_500()
     }
 
-    pub async fn broadcast_rawtx(&self, rawtx: &Vec<u8>) -> Result<BroadcastResponse> {
-        let body = json!({
-            "method": "sendrawtransaction".to_string(),
-            "params": [hex::encode(rawtx)],
-            "id": "420".to_string(),
-        });
-
-        let res = self.client().json(&body).send().await?;
-        let status = (&res).status().clone();
-        let body = res.json::<Value>().await?;
+    fn next_id(&self) -> u64 {
+        self.last_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
 
-        return Ok(BroadcastResponse {
-            success: status == reqwest::StatusCode::OK,
-            response: body,
-        });
+    fn body(&self, method: &str, params: &Value) -> Value {
+        json!({
+            "method": method,
+            "params": params,
+            "id": self.next_id(),
+        })
     }
 
-    pub async fn get_blockchain_info(&self) -> Result<RPCBlockchainInfoResponse> {
-        let body = json!({
-            "method": "getblockchaininfo".to_string(),
-            "id": "420".to_string(),
-        });
+    /// Calls `method`, retrying on transport errors unless it changes node state.
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<RPCResponse<T>> {
+        let mut request = self.client().json(&self.body(method, &params));
+        if method != "sendrawtransaction" {
+            request = request.idempotent();
+        }
 
-        let res = self
-            .client()
-            .json(&body)
-            .send()
-            .await?
-            .json::<RPCBlockchainInfoResponse>()
-            .await?;
+        let res = request.send().await?.json::<RPCResponse<T>>().await?;
 
-        return Ok(res);
+        Ok(res)
     }
 
-    pub async fn get_block_header(&self, block_hash: String) -> Result<RPCBlockHashResponse> {
-        let body = json!({
-            "method": "getblockheader".to_string(),
-            "id": "420".to_string(),
-            "params": [block_hash, false],
-        });
+    /// Sends `calls` in one request, answers come back in the order of `calls`.
+    pub async fn batch(&self, calls: &[RPCCall]) -> Result<Vec<RPCResponse<Value>>> {
+        let body: Vec<Value> = calls
+            .iter()
+            .map(|e| self.body(&e.method, &e.params))
+            .collect();
+        let ids: Vec<Value> = body.iter().map(|e| e["id"].clone()).collect();
+
+        let mut request = self.client().json(&body);
+        if calls.iter().all(|e| e.method != "sendrawtransaction") {
+            request = request.idempotent();
+        }
 
-        let res = self
-            .client()
-            .json(&body)
+        let mut res = request
             .send()
             .await?
-            .json::<RPCBlockHashResponse>()
+            .json::<Vec<RPCResponse<Value>>>()
             .await?;
 
+        // nodes may answer a batch in any order
+        res.sort_by_key(|e| ids.iter().position(|id| *id == e.id));
+
         Ok(res)
     }
 
-    pub async fn get_block_hash(&self, height: i64) -> Result<RPCBlockHashResponse> {
-        let body = json!({
-            "method": "getblockhash".to_string(),
-            "id": "420".to_string(),
-            "params": [height],
+    /// Nodes answer rejected transactions with a 500 and the reason in `error`.
+    pub async fn send_rawtransaction(&self, rawtx: &Vec<u8>) -> Result<RPCBroadcastResponse> {
+        self.call("sendrawtransaction", json!([hex::encode(rawtx)]))
+            .await
+    }
+
+    pub async fn broadcast_rawtx(&self, rawtx: &Vec<u8>) -> Result<BroadcastResponse> {
+        let res = self.send_rawtransaction(rawtx).await?;
+
+        return Ok(BroadcastResponse {
+            success: res.status().is_success(),
+            response: serde_json::to_value(res)?,
         });
+    }
 
-        let res = self
-            .client()
-            .json(&body)
-            .send()
-            .await?
-            .json::<RPCBlockHashResponse>()
-            .await?;
+    pub async fn get_blockchain_info(&self) -> Result<RPCBlockchainInfoResponse> {
+        self.call("getblockchaininfo", json!([])).await
+    }
 
-        return Ok(res);
+    pub async fn get_block_header(&self, block_hash: String) -> Result<RPCBlockHashResponse> {
+        self.call("getblockheader", json!([block_hash, false]))
+            .await
+    }
+
+    pub async fn get_block_hash(&self, height: i64) -> Result<RPCBlockHashResponse> {
+        self.call("getblockhash", json!([height])).await
     }
 
     pub async fn get_block_raw(&self, block_hash: String) -> Result<RPCRawBlockResponse> {
-        let body = json!( {
-            "method": "getblock".to_string(),
-            "id": "420".to_string(),
-            "params": [block_hash, false],
-        });
+        self.call("getblock", json!([block_hash, false])).await
+    }
 
-        let res = self
-            .client()
-            .json(&body)
-            .send()
-            .await?
-            .json::<RPCRawBlockResponse>()
-            .await?;
+    pub async fn get_best_block_hash(&self) -> Result<RPCResponse<String>> {
+        self.call("getbestblockhash", json!([])).await
+    }
+
+    pub async fn get_raw_transaction(&self, txid: &str) -> Result<RPCResponse<String>> {
+        self.call("getrawtransaction", json!([txid, false])).await
+    }
+
+    pub async fn get_raw_transaction_verbose(
+        &self,
+        txid: &str,
+    ) -> Result<RPCResponse<RPCRawTransaction>> {
+        self.call("getrawtransaction", json!([txid, true])).await
+    }
+
+    pub async fn get_raw_mempool(&self) -> Result<RPCResponse<Vec<String>>> {
+        self.call("getrawmempool", json!([false])).await
+    }
+
+    pub async fn get_mempool_entry(&self, txid: &str) -> Result<RPCResponse<RPCMempoolEntry>> {
+        self.call("getmempoolentry", json!([txid])).await
+    }
+
+    /// `result` is `None` without an error once the output is spent.
+    pub async fn get_tx_out(
+        &self,
+        txid: &str,
+        vout: u32,
+        include_mempool: bool,
+    ) -> Result<RPCResponse<RPCTxOut>> {
+        self.call("gettxout", json!([txid, vout, include_mempool]))
+            .await
+    }
+
+    pub async fn test_mempool_accept(
+        &self,
+        rawtxs: &[Vec<u8>],
+    ) -> Result<RPCResponse<Vec<RPCMempoolAccept>>> {
+        let rawtxs: Vec<String> = rawtxs.iter().map(hex::encode).collect();
+        self.call("testmempoolaccept", json!([rawtxs])).await
+    }
+
+    pub async fn verify_script(
+        &self,
+        scripts: &[RPCVerifyScript],
+    ) -> Result<RPCResponse<Vec<RPCVerifyScriptResult>>> {
+        self.call("verifyscript", json!([scripts])).await
+    }
 
-        return Ok(res);
+    pub async fn get_merkle_proof(&self, txid: &str) -> Result<RPCResponse<RPCMerkleProof>> {
+        self.call("getmerkleproof", json!([txid])).await
     }
 }
@@ -0,0 +1,324 @@
+use crate::{constants, TwetchError};
+use anyhow::Result;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    /// Not supported by browsers, requests there run until the browser gives up.
+    pub timeout_ms: Option<u64>,
+    /// Retries of idempotent requests after a failed first attempt.
+    pub retries: u32,
+    /// Delay before the first retry, doubling for every one after it.
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Browsers send their own.
+    pub user_agent: String,
+    /// Added to every request.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for TransportConfig {
+    fn default() -> TransportConfig {
+        TransportConfig {
+            timeout_ms: Some(constants::HTTP_TIMEOUT_MS),
+            retries: constants::HTTP_RETRIES,
+            backoff_ms: constants::HTTP_BACKOFF_MS,
+            max_backoff_ms: constants::HTTP_MAX_BACKOFF_MS,
+            user_agent: format!("twetch-sdk/{}", env!("CARGO_PKG_VERSION")),
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl TransportConfig {
+    pub fn backoff(&self, attempt: u32) -> u64 {
+        self.backoff_ms
+            .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff_ms)
+    }
+}
+
+/// One attempt at a request, `status` is `None` when no response came back.
+#[derive(Debug, Clone)]
+pub struct TransportEvent {
+    pub method: String,
+    pub url: String,
+    pub attempt: u32,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+pub trait TransportLogger: Send + Sync {
+    fn log(&self, event: &TransportEvent);
+}
+
+/// HTTP client shared by the API clients, pooling connections and retrying
+/// idempotent requests with exponential backoff. Outside the browser requests
+/// have to be sent from within a Tokio runtime with its time driver enabled,
+/// such as `#[tokio::main]`, which reqwest and the backoff timer run on.
+#[derive(Clone)]
+pub struct HttpTransport {
+    /// Why the client couldn't be built, reported by every request.
+    client: std::result::Result<reqwest::Client, String>,
+    config: TransportConfig,
+    logger: Option<Arc<dyn TransportLogger>>,
+}
+
+static SHARED: OnceLock<HttpTransport> = OnceLock::new();
+
+impl HttpTransport {
+    pub fn new(config: TransportConfig) -> Result<HttpTransport> {
+        let transport = HttpTransport::build(config);
+        match &transport.client {
+            Ok(_) => Ok(transport),
+            Err(e) => anyhow::bail!("HTTP Error: {}", e),
+        }
+    }
+
+    fn build(config: TransportConfig) -> HttpTransport {
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = {
+            let mut builder = reqwest::Client::builder().user_agent(config.user_agent.clone());
+            if let Some(v) = config.timeout_ms {
+                builder = builder.timeout(std::time::Duration::from_millis(v));
+            }
+            builder.build().map_err(|e| e.to_string())
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        let client = Ok(reqwest::Client::new());
+
+        HttpTransport {
+            client,
+            config,
+            logger: None,
+        }
+    }
+
+    /// Transport the API clients use unless given another one. When the
+    /// client can't be built its requests fail with the reason.
+    pub fn shared() -> HttpTransport {
+        SHARED
+            .get_or_init(|| HttpTransport::build(TransportConfig::default()))
+            .clone()
+    }
+
+    /// Replaces the transport `shared` returns, only works before its first use.
+    pub fn set_shared(transport: HttpTransport) -> Result<()> {
+        match SHARED.set(transport) {
+            Ok(_) => Ok(()),
+            Err(_) => anyhow::bail!("the shared transport is already in use"),
+        }
+    }
+
+    pub fn with_logger(&self, logger: Arc<dyn TransportLogger>) -> HttpTransport {
+        HttpTransport {
+            logger: Some(logger),
+            ..self.clone()
+        }
+    }
+
+    pub fn config(&self) -> &TransportConfig {
+        &self.config
+    }
+
+    /// GETs are retried, other methods only once marked `idempotent`.
+    pub fn request(&self, method: Method, url: String) -> TransportRequest {
+        let builder = self.client.clone().map(|client| {
+            let mut builder = client.request(method.clone(), &url);
+            for (k, v) in &self.config.headers {
+                builder = builder.header(k, v);
+            }
+            builder
+        });
+
+        TransportRequest {
+            transport: self.clone(),
+            idempotent: method == Method::GET,
+            retry_500: true,
+            method,
+            url,
+            builder,
+        }
+    }
+
+    pub fn get(&self, url: String) -> TransportRequest {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: String) -> TransportRequest {
+        self.request(Method::POST, url)
+    }
+
+    fn log(&self, event: TransportEvent) {
+        if let Some(logger) = &self.logger {
+            logger.log(&event);
+        }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> HttpTransport {
+        HttpTransport::shared()
+    }
+}
+
+impl fmt::Debug for HttpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpTransport")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+pub struct TransportRequest {
+    transport: HttpTransport,
+    method: Method,
+    url: String,
+    builder: std::result::Result<RequestBuilder, String>,
+    idempotent: bool,
+    retry_500: bool,
+}
+
+impl TransportRequest {
+    pub fn header(mut self, key: &str, value: impl AsRef<str>) -> TransportRequest {
+        self.builder = self.builder.map(|e| e.header(key, value.as_ref()));
+        self
+    }
+
+    pub fn basic_auth(mut self, user: &str, password: Option<&str>) -> TransportRequest {
+        self.builder = self.builder.map(|e| e.basic_auth(user, password));
+        self
+    }
+
+    pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> TransportRequest {
+        self.builder = self.builder.map(|e| e.json(json));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<reqwest::Body>) -> TransportRequest {
+        self.builder = self.builder.map(|e| e.body(body));
+        self
+    }
+
+    /// Safe to send again when a response got lost, such as a lookup sent as a POST.
+    pub fn idempotent(mut self) -> TransportRequest {
+        self.idempotent = true;
+        self
+    }
+
+    /// Takes a 500 as the answer instead of retrying it, for JSON-RPC nodes
+    /// that report failed calls with one. Other server errors are still retried.
+    pub fn final_on_500(mut self) -> TransportRequest {
+        self.retry_500 = false;
+        self
+    }
+
+    pub async fn send(self) -> Result<Response> {
+        let transport = self.transport;
+        let attempts = match self.idempotent {
+            true => transport.config.retries + 1,
+            false => 1,
+        };
+
+        let mut builder = match self.builder {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(TwetchError::Http {
+                    url: Some(self.url),
+                    status: None,
+                    message: e,
+                }
+                .into())
+            }
+        };
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let next = match attempt < attempts {
+                true => builder.try_clone(),
+                false => None,
+            };
+
+            let res = builder.send().await;
+            let retryable = match &res {
+                Ok(v) if v.status() == StatusCode::INTERNAL_SERVER_ERROR => self.retry_500,
+                Ok(v) => {
+                    v.status().is_server_error() || v.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => !e.is_builder() && !e.is_decode() && !e.is_status(),
+            };
+
+            transport.log(TransportEvent {
+                method: self.method.to_string(),
+                url: self.url.clone(),
+                attempt,
+                status: res.as_ref().ok().map(|e| e.status().as_u16()),
+                error: res.as_ref().err().map(|e| e.to_string()),
+            });
+
+            match next {
+                Some(v) if retryable => {
+                    sleep(transport.config.backoff(attempt)).await;
+                    builder = v;
+                }
+                _ => return res.map_err(|e| TwetchError::from(&e).into()),
+            }
+        }
+    }
+
+    /// Same as `send`, failing with a `TwetchError::Http` that carries the
+    /// status when the response isn't a 2xx. For APIs whose error responses
+    /// don't share the shape of their answers.
+    pub async fn send_checked(self) -> Result<Response> {
+        let url = self.url.clone();
+        let res = self.send().await?;
+
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let message = match res.text().await {
+            Ok(v) if !v.is_empty() => v,
+            _ => status.canonical_reason().unwrap_or_default().to_string(),
+        };
+
+        Err(TwetchError::Http {
+            url: Some(url),
+            status: Some(status.as_u16()),
+            message,
+        }
+        .into())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(ms: u64) {
+    tokio::time::sleep(std::time::Duration::from_millis(ms)).await
+}
+
+// setTimeout of the window or worker, retries go out right away without one
+#[cfg(target_arch = "wasm32")]
+async fn sleep(ms: u64) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let global = js_sys::global();
+        let set_timeout = js_sys::Reflect::get(&global, &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|e| e.dyn_into::<js_sys::Function>().ok());
+
+        let _ = match set_timeout {
+            Some(f) => f.call2(&global, &resolve, &JsValue::from_f64(ms as f64)),
+            None => resolve.call0(&JsValue::UNDEFINED),
+        };
+    });
+
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
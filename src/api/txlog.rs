@@ -1,24 +1,32 @@
+use crate::{HttpTransport, TransportRequest};
 use anyhow::Result;
 use bsv::Script;
 
 pub struct TxlogApi {
     url: String,
+    transport: HttpTransport,
 }
 
 impl TxlogApi {
     pub fn new(url: String) -> TxlogApi {
-        TxlogApi { url }
+        TxlogApi {
+            url,
+            transport: HttpTransport::shared(),
+        }
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.get(format!("{}{}", self.url, path))
+    pub fn with_transport(self, transport: HttpTransport) -> TxlogApi {
+        TxlogApi { transport, ..self }
+    }
+
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport.get(format!("{}{}", self.url, path))
     }
 
     pub async fn rawtx(&self, txid: &String) -> Result<String> {
         let res = self
             .get(format!("/tx/{}/raw", txid))
-            .send()
+            .send_checked()
             .await?
             .text()
             .await?;
@@ -29,7 +37,7 @@ impl TxlogApi {
     pub async fn script(&self, txid: &String, vout: u32) -> Result<Script> {
         let res = self
             .get(format!("/tx/{}/{}/script", txid, vout))
-            .send()
+            .send_checked()
             .await?
             .text()
             .await?;
@@ -40,7 +48,7 @@ impl TxlogApi {
     pub async fn satoshis(&self, txid: &String, vout: u32) -> Result<u64> {
         let res = self
             .get(format!("/tx/{}/{}/satoshis", txid, vout))
-            .send()
+            .send_checked()
             .await?
             .text()
             .await?
@@ -1,3 +1,4 @@
+use crate::{HttpTransport, TransportRequest, TwetchError};
 use anyhow::Result;
 use bsv::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -61,27 +62,34 @@ pub struct MempoolCheckResponse {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UtxoDetectiveApi {
     url: String,
+    #[serde(skip)]
+    transport: HttpTransport,
 }
 
 impl UtxoDetectiveApi {
     pub fn new(url: String) -> UtxoDetectiveApi {
-        UtxoDetectiveApi { url }
+        UtxoDetectiveApi {
+            url,
+            transport: HttpTransport::shared(),
+        }
+    }
+
+    pub fn with_transport(self, transport: HttpTransport) -> UtxoDetectiveApi {
+        UtxoDetectiveApi { transport, ..self }
     }
 
-    pub fn post(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.post(format!("{}{}", self.url, path))
+    pub fn post(&self, path: String) -> TransportRequest {
+        self.transport.post(format!("{}{}", self.url, path))
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.get(format!("{}{}", self.url, path))
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport.get(format!("{}{}", self.url, path))
     }
 
     pub async fn balance_by_address(&self, address: &String) -> Result<UtxoDetectiveBalance> {
         let res = self
             .get(format!("/balance/{}", address))
-            .send()
+            .send_checked()
             .await?
             .json::<UtxoDetectiveBalance>()
             .await?;
@@ -96,8 +104,9 @@ impl UtxoDetectiveApi {
 
         let res = self
             .post(format!("/outpoints"))
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<Vec<bool>>()
             .await?;
@@ -112,8 +121,9 @@ impl UtxoDetectiveApi {
 
         let res = self
             .post(format!("/mempool/spends"))
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<Vec<bool>>()
             .await?;
@@ -139,8 +149,9 @@ impl UtxoDetectiveApi {
 
         let response = self
             .post(format!("/mempool"))
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<MempoolCheckResponse>()
             .await?;
@@ -158,8 +169,9 @@ impl UtxoDetectiveApi {
 
         let res = self
             .post(format!("/spends/values"))
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<UtxoDetectiveSpentOutpointResponse>()
             .await?;
@@ -168,10 +180,17 @@ impl UtxoDetectiveApi {
             .outpoints
             .iter()
             .map(|e| match e {
-                Some(v) => Some((hex::decode(&v.o).unwrap(), v.h)),
-                None => None,
+                Some(v) => match hex::decode(&v.o) {
+                    Ok(txid) => Ok(Some((txid, v.h))),
+                    Err(_) => Err(TwetchError::api(
+                        "utxo detective",
+                        vec![format!("invalid spending txid {}", v.o)],
+                    )
+                    .into()),
+                },
+                None => Ok(None),
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(response)
     }
@@ -179,7 +198,7 @@ impl UtxoDetectiveApi {
     pub async fn utxos_by_address(&self, address: &String) -> Result<Vec<UtxoDetectivePublicUtxo>> {
         let res = self
             .get(format!("/utxos/{}", address))
-            .send()
+            .send_checked()
             .await?
             .json::<Vec<UtxoDetectivePublicUtxo>>()
             .await?;
@@ -194,8 +213,9 @@ impl UtxoDetectiveApi {
 
         let res = self
             .post("/sync/decode-tx".to_string())
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<UtxoDetectiveDecodeTxResponse>()
             .await?;
@@ -211,7 +231,7 @@ impl UtxoDetectiveApi {
         let res = self
             .post("/sync/tx".to_string())
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<Value>()
             .await?;
@@ -231,8 +251,9 @@ impl UtxoDetectiveApi {
 
         let res = self
             .post("/metasync/wallet/utxos".to_string())
+            .idempotent()
             .json(&payload)
-            .send()
+            .send_checked()
             .await?
             .json::<UtxoDetectiveUTXOResponse>()
             .await?;
@@ -1,4 +1,4 @@
-use crate::Networks;
+use crate::{HttpTransport, Networks, TransportRequest};
 use anyhow::Result;
 use bsv::P2PKHAddress;
 use serde::{Deserialize, Serialize};
@@ -8,15 +8,25 @@ pub struct WhatsOnChainUTXO {
     pub tx_hash: String,
     pub tx_pos: u32,
     pub value: u64,
+    #[serde(default)]
+    pub height: Option<i64>,
 }
 
 pub struct WhatsOnChainApi {
     url: String,
+    transport: HttpTransport,
 }
 
 impl WhatsOnChainApi {
     pub fn new(url: String) -> WhatsOnChainApi {
-        WhatsOnChainApi { url }
+        WhatsOnChainApi {
+            url,
+            transport: HttpTransport::shared(),
+        }
+    }
+
+    pub fn with_transport(self, transport: HttpTransport) -> WhatsOnChainApi {
+        WhatsOnChainApi { transport, ..self }
     }
 
     pub fn network(network: &Networks) -> String {
@@ -26,9 +36,8 @@ impl WhatsOnChainApi {
         }
     }
 
-    pub fn get(&self, path: String) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
-        client.get(format!("{}{}", self.url, path))
+    pub fn get(&self, path: String) -> TransportRequest {
+        self.transport.get(format!("{}{}", self.url, path))
     }
 
     pub async fn utxos(
@@ -44,7 +53,7 @@ impl WhatsOnChainApi {
                 WhatsOnChainApi::network(network),
                 scripthash
             ))
-            .send()
+            .send_checked()
             .await?
             .json::<Vec<WhatsOnChainUTXO>>()
             .await?;
@@ -1,3 +1,4 @@
+use crate::TwetchError;
 use anyhow::Result;
 use bsv::{hash::Hash, AESAlgorithms, PBKDF2Hashes, AES, KDF};
 use serde::{Deserialize, Serialize};
@@ -33,7 +34,10 @@ macro_rules! expect_two {
 
 impl AuthToken {
     pub fn new(token: String) -> Result<AuthToken> {
-        let user = AuthToken::decode(&token)?;
+        let user = match AuthToken::decode(&token) {
+            Ok(v) => v,
+            Err(e) => return Err(TwetchError::Auth(format!("invalid token, {}", e)).into()),
+        };
         Ok(AuthToken {
             token,
             user_id: user.id,
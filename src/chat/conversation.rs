@@ -1,4 +1,4 @@
-use crate::{constants, GraphqlApi, Message, Wallet};
+use crate::{GraphqlApi, Message, SdkConfig, Wallet};
 use anyhow::Result;
 use base64;
 use bsv::{ECIESCiphertext, Hash, PrivateKey, PublicKey, ECIES};
@@ -12,8 +12,12 @@ pub struct Conversation {
 }
 
 impl Conversation {
-    pub async fn create(token: String, user_ids: Vec<String>) -> Result<Conversation> {
-        let api = GraphqlApi::new(constants::GATEWAY_URL.to_string(), token);
+    pub async fn create(
+        token: String,
+        user_ids: Vec<String>,
+        config: &SdkConfig,
+    ) -> Result<Conversation> {
+        let api = GraphqlApi::new(config.gateway_url.clone(), token);
 
         let pubkeys = api
             .list_pubkeys(user_ids.clone())
@@ -54,19 +58,30 @@ impl Conversation {
         })
     }
 
-    pub async fn set_name(token: String, conversation: String, name: String) -> Result<()> {
-        let api = GraphqlApi::new(constants::GATEWAY_URL.to_string(), token);
+    pub async fn set_name(
+        token: String,
+        conversation: String,
+        name: String,
+        config: &SdkConfig,
+    ) -> Result<()> {
+        let api = GraphqlApi::new(config.gateway_url.clone(), token);
         api.update_conversation(conversation, name).await?;
         Ok(())
     }
 
-    pub async fn create_message(&self, token: String, description: String) -> Result<Message> {
+    pub async fn create_message(
+        &self,
+        token: String,
+        description: String,
+        config: &SdkConfig,
+    ) -> Result<Message> {
         Message::create(
             self.key.clone(),
             self.id.clone(),
             "1".to_string(),
             description,
             token,
+            config,
         )
         .await
     }
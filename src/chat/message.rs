@@ -1,4 +1,4 @@
-use crate::{constants, GraphqlApi};
+use crate::{GraphqlApi, SdkConfig};
 use anyhow::Result;
 use bsv::{AESAlgorithms, AES};
 use js_sys::decode_uri_component;
@@ -44,6 +44,7 @@ impl Message {
         user_id: String,
         description: String,
         token: String,
+        config: &SdkConfig,
     ) -> Result<Message> {
         let encrypted = Message::encrypt(&hex::decode(key).unwrap(), description.clone())?;
 
@@ -55,7 +56,7 @@ impl Message {
             }
         });
 
-        let api = GraphqlApi::new(constants::GATEWAY_URL.to_string(), token);
+        let api = GraphqlApi::new(config.gateway_url.clone(), token);
         api.create_message(payload).await?;
 
         Ok(Message {
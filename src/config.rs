@@ -0,0 +1,106 @@
+use crate::{constants, Networks};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// Endpoints and defaults of the services the SDK talks to. Wallets carry
+/// their own, see `Wallet::with_config`, everything else reads `SdkConfig::current`.
+/// `network` is the default of `Networks` and of `Wallet::network`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SdkConfig {
+    pub api_url: String,
+    pub gateway_url: String,
+    pub metasync_url: String,
+    pub polynym_url: String,
+    pub txlog_url: String,
+    pub dogefiles_url: String,
+    pub utxo_detective_url: String,
+    pub whatsonchain_url: String,
+    /// Identifies the app to the Twetch API when asking for payees.
+    pub client_identifier: String,
+    pub network: Networks,
+}
+
+impl Default for SdkConfig {
+    fn default() -> SdkConfig {
+        SdkConfig {
+            api_url: constants::API_URL.to_string(),
+            gateway_url: constants::GATEWAY_URL.to_string(),
+            metasync_url: constants::METASYNC_URL.to_string(),
+            polynym_url: constants::POLYNYM_URL.to_string(),
+            txlog_url: constants::TXLOG_URL.to_string(),
+            dogefiles_url: constants::DOGEFILES_URL.to_string(),
+            utxo_detective_url: constants::UTXO_DETECTIVE_URL.to_string(),
+            whatsonchain_url: constants::WHATSONCHAIN_URL.to_string(),
+            client_identifier: constants::CLIENT_IDENTIFIER.to_string(),
+            network: Networks::BSV,
+        }
+    }
+}
+
+static CURRENT: RwLock<Option<SdkConfig>> = RwLock::new(None);
+
+impl SdkConfig {
+    pub fn testnet() -> SdkConfig {
+        SdkConfig {
+            network: Networks::TBSV,
+            ..Default::default()
+        }
+    }
+
+    /// Every endpoint at `url`, for running against a single local stand-in.
+    pub fn local(url: &str) -> SdkConfig {
+        SdkConfig {
+            api_url: url.to_string(),
+            gateway_url: url.to_string(),
+            metasync_url: url.to_string(),
+            polynym_url: url.to_string(),
+            txlog_url: url.to_string(),
+            dogefiles_url: url.to_string(),
+            utxo_detective_url: url.to_string(),
+            whatsonchain_url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Defaults overridden by `TWETCH_API_URL`, `TWETCH_GATEWAY_URL`,
+    /// `TWETCH_METASYNC_URL`, `TWETCH_POLYNYM_URL`, `TWETCH_TXLOG_URL`,
+    /// `TWETCH_DOGEFILES_URL`, `TWETCH_UTXO_DETECTIVE_URL`,
+    /// `TWETCH_WHATSONCHAIN_URL`, `TWETCH_CLIENT_IDENTIFIER` and `TWETCH_NETWORK`
+    /// (`BSV` or `TBSV`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_env() -> Result<SdkConfig> {
+        let var = |name: &str, default: String| std::env::var(name).unwrap_or(default);
+        let config = SdkConfig::default();
+
+        Ok(SdkConfig {
+            api_url: var("TWETCH_API_URL", config.api_url),
+            gateway_url: var("TWETCH_GATEWAY_URL", config.gateway_url),
+            metasync_url: var("TWETCH_METASYNC_URL", config.metasync_url),
+            polynym_url: var("TWETCH_POLYNYM_URL", config.polynym_url),
+            txlog_url: var("TWETCH_TXLOG_URL", config.txlog_url),
+            dogefiles_url: var("TWETCH_DOGEFILES_URL", config.dogefiles_url),
+            utxo_detective_url: var("TWETCH_UTXO_DETECTIVE_URL", config.utxo_detective_url),
+            whatsonchain_url: var("TWETCH_WHATSONCHAIN_URL", config.whatsonchain_url),
+            client_identifier: var("TWETCH_CLIENT_IDENTIFIER", config.client_identifier),
+            network: match std::env::var("TWETCH_NETWORK") {
+                Ok(v) if v == "BSV" || v == "TBSV" => v.into(),
+                Ok(v) => anyhow::bail!("unknown TWETCH_NETWORK {}", v),
+                Err(_) => config.network,
+            },
+        })
+    }
+
+    /// Config of new wallets and of calls made without one, `Default` until set.
+    pub fn current() -> SdkConfig {
+        match CURRENT.read().unwrap().as_ref() {
+            Some(v) => v.clone(),
+            None => SdkConfig::default(),
+        }
+    }
+
+    pub fn set_current(config: SdkConfig) {
+        *CURRENT.write().unwrap() = Some(config);
+    }
+}
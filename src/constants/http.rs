@@ -0,0 +1,7 @@
+pub const HTTP_TIMEOUT_MS: u64 = 30000;
+// Retries of idempotent requests, on top of the first attempt
+pub const HTTP_RETRIES: u32 = 2;
+pub const HTTP_BACKOFF_MS: u64 = 250;
+pub const HTTP_MAX_BACKOFF_MS: u64 = 5000;
+// Sent to the Twetch API as `client_identifier`
+pub const CLIENT_IDENTIFIER: &str = "1325c30a-7eb3-4169-a6f4-330eeeb8ca49";
@@ -3,3 +3,6 @@ pub use urls::*;
 
 pub mod transaction;
 pub use transaction::*;
+
+pub mod http;
+pub use http::*;
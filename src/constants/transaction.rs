@@ -1,5 +1,13 @@
 pub const TX_FEE_RATE: f64 = 0.25;
 pub const MIN_TX_FEE_RATE: f64 = 0.1;
+// Smaller change is left to the miner rather than creating dust
+pub const MIN_CHANGE_SATS: u64 = 100;
+pub const UTXO_RESERVATION_TIMEOUT_MS: u64 = 120000;
+// Local spends the indexer hasn't seen by then never made it to the network
+pub const UNSEEN_SPEND_TIMEOUT_MS: u64 = 600000;
+// Change keys are derived from this index on under the wallet xpub, far past
+// the paths the paymail server hands out for incoming payments
+pub const CHANGE_PATH_START: i32 = 1 << 30;
 // Either 106 or 107 bytes
 pub const P2PKH_UNLOCKING_SCRIPT_SIZE: usize = 107;
 pub const P2PKH_OUTPUT_SIZE: usize = 34;
@@ -0,0 +1,112 @@
+use crate::RecipientError;
+use std::fmt;
+
+/// What went wrong, sorted into the cases an app shows differently.
+///
+/// Sdk functions keep returning `anyhow::Result`; downcast the error or call
+/// `TwetchError::from_error` to branch on the category.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TwetchError {
+    InsufficientFunds {
+        required: u64,
+        available: u64,
+    },
+    Resolution(String),
+    Http {
+        url: Option<String>,
+        status: Option<u16>,
+        message: String,
+    },
+    Api {
+        service: String,
+        errors: Vec<String>,
+    },
+    /// The fee a build ended up with is below what the fee policy allows.
+    Fee(String),
+    Signing(String),
+    Abi(String),
+    Auth(String),
+}
+
+impl TwetchError {
+    pub fn api(service: &str, errors: Vec<String>) -> TwetchError {
+        TwetchError::Api {
+            service: service.to_string(),
+            errors,
+        }
+    }
+
+    /// Finds the typed error anywhere in the chain, treating a failed recipient
+    /// lookup as a resolution failure and a bare reqwest error as HTTP.
+    pub fn from_error(err: &anyhow::Error) -> Option<TwetchError> {
+        // context attached with `Error::context` is only reachable from the top
+        if let Some(v) = err.downcast_ref::<TwetchError>() {
+            return Some(v.clone());
+        }
+
+        for e in err.chain() {
+            if let Some(v) = e.downcast_ref::<TwetchError>() {
+                return Some(v.clone());
+            }
+
+            if let Some(v) = e.downcast_ref::<RecipientError>() {
+                return Some(TwetchError::Resolution(v.to_string()));
+            }
+
+            if let Some(v) = e.downcast_ref::<reqwest::Error>() {
+                return Some(TwetchError::from(v));
+            }
+        }
+
+        None
+    }
+}
+
+impl From<&reqwest::Error> for TwetchError {
+    fn from(v: &reqwest::Error) -> TwetchError {
+        TwetchError::Http {
+            url: v.url().map(|e| e.to_string()),
+            status: v.status().map(|e| e.as_u16()),
+            message: v.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TwetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TwetchError::InsufficientFunds {
+                required,
+                available,
+            } => write!(
+                f,
+                "Insufficient Funds Error: requires {} sats, {} available",
+                required, available
+            ),
+            TwetchError::Resolution(v) => write!(f, "Resolution Error: {}", v),
+            TwetchError::Http {
+                url,
+                status,
+                message,
+            } => {
+                write!(f, "HTTP Error: ")?;
+                if let Some(v) = status {
+                    write!(f, "{} ", v)?;
+                }
+                if let Some(v) = url {
+                    write!(f, "from {} ", v)?;
+                }
+                write!(f, "{}", message)
+            }
+            TwetchError::Api { service, errors } => {
+                write!(f, "API Error: {} {}", service, errors.join(" "))
+            }
+            TwetchError::Fee(v) => write!(f, "Fee Error: {}", v),
+            TwetchError::Signing(v) => write!(f, "Signing Error: {}", v),
+            TwetchError::Abi(v) => write!(f, "ABI Error: {}", v),
+            TwetchError::Auth(v) => write!(f, "Auth Error: {}", v),
+        }
+    }
+}
+
+impl std::error::Error for TwetchError {}
@@ -7,6 +7,12 @@ pub use authentication::*;
 pub mod chat;
 pub use chat::*;
 
+pub mod config;
+pub use config::*;
+
+pub mod error;
+pub use error::*;
+
 pub mod post;
 pub use post::*;
 
@@ -1,4 +1,4 @@
-use crate::{constants, MetasyncApi};
+use crate::{MetasyncApi, SdkConfig, TwetchError};
 use anyhow::Result;
 use bsv::{P2PKHAddress, Script};
 use hex::FromHex;
@@ -28,61 +28,104 @@ pub enum SigilABIMethods {
     Slurp,
 }
 
-impl From<SigilABIParam> for TXID {
-    fn from(v: SigilABIParam) -> TXID {
-        TXID::from_hex(v.value.as_str().unwrap().to_string()).unwrap()
+impl SigilABIParam {
+    fn as_str(&self) -> Result<&str, TwetchError> {
+        match self.value.as_str() {
+            Some(v) => Ok(v),
+            None => Err(TwetchError::Abi(format!(
+                "expected a string param, got {}",
+                self.value
+            ))),
+        }
     }
 }
 
-impl From<SigilABIParam> for String {
-    fn from(v: SigilABIParam) -> String {
-        v.value.as_str().unwrap().to_string()
+impl TryFrom<SigilABIParam> for TXID {
+    type Error = TwetchError;
+
+    fn try_from(v: SigilABIParam) -> Result<TXID, TwetchError> {
+        let txid = v.as_str()?;
+        TXID::from_hex(txid).map_err(|e| abi_error("txid", txid, e))
     }
 }
 
-impl From<SigilABIParam> for Script {
-    fn from(v: SigilABIParam) -> Script {
-        let address: String = v.into();
-        P2PKHAddress::from_string(&address)
-            .unwrap()
-            .get_locking_script()
-            .unwrap()
+impl TryFrom<SigilABIParam> for String {
+    type Error = TwetchError;
+
+    fn try_from(v: SigilABIParam) -> Result<String, TwetchError> {
+        Ok(v.as_str()?.to_string())
     }
 }
 
-impl From<SigilABIParam> for u64 {
-    fn from(v: SigilABIParam) -> u64 {
-        v.value.as_u64().unwrap()
+impl TryFrom<SigilABIParam> for Script {
+    type Error = TwetchError;
+
+    fn try_from(v: SigilABIParam) -> Result<Script, TwetchError> {
+        let address = v.as_str()?;
+        P2PKHAddress::from_string(address)
+            .and_then(|e| e.get_locking_script())
+            .map_err(|e| abi_error("address", address, e))
     }
 }
 
-impl From<SigilABIParam> for Outpoint {
-    fn from(v: SigilABIParam) -> Outpoint {
-        let outpoint: String = v.into();
-        Outpoint::from_hex(outpoint).unwrap()
+impl TryFrom<SigilABIParam> for u64 {
+    type Error = TwetchError;
+
+    fn try_from(v: SigilABIParam) -> Result<u64, TwetchError> {
+        match v.value.as_u64() {
+            Some(v) => Ok(v),
+            None => Err(TwetchError::Abi(format!(
+                "expected an amount param, got {}",
+                v.value
+            ))),
+        }
     }
 }
 
-pub fn get_mint_utos(v: SigilABIParam, contract: String) -> Vec<UTO> {
-    let values: Vec<Value> = serde_json::from_str(v.value.as_str().unwrap()).unwrap();
+impl TryFrom<SigilABIParam> for Outpoint {
+    type Error = TwetchError;
+
+    fn try_from(v: SigilABIParam) -> Result<Outpoint, TwetchError> {
+        let outpoint = v.as_str()?;
+        Outpoint::from_hex(outpoint).map_err(|e| abi_error("outpoint", outpoint, e))
+    }
+}
 
-    let utos: Vec<UTO> = values
+fn abi_error(kind: &str, value: &str, e: impl std::fmt::Display) -> TwetchError {
+    TwetchError::Abi(format!("invalid {} '{}', {}", kind, value, e))
+}
+
+pub fn get_mint_utos(v: SigilABIParam, contract: String) -> Result<Vec<UTO>, TwetchError> {
+    let list = v.as_str()?;
+    let values: Vec<Value> =
+        serde_json::from_str(list).map_err(|e| abi_error("mint list", list, e))?;
+    let contract = TXID::from_hex(&contract).map_err(|e| abi_error("contract", &contract, e))?;
+
+    values
         .iter()
-        .map(|e| UTO {
-            outpoint: Outpoint([0u8; 36]),
-            satoshis: 2180,
-            contract: TXID::from_hex(contract.clone()).unwrap(),
-            token: hex::decode(e.get("token").unwrap().as_str().unwrap()).unwrap(),
-            script: Script::from_hex(e.get("script").unwrap().as_str().unwrap()).unwrap(),
-            value: None,
-        })
-        .collect();
+        .map(|e| {
+            let field = |name: &str| match e.get(name).and_then(|e| e.as_str()) {
+                Some(v) => Ok(v),
+                None => Err(TwetchError::Abi(format!("mint is missing '{}'", name))),
+            };
+
+            let token = field("token")?;
+            let script = field("script")?;
 
-    utos
+            Ok(UTO {
+                outpoint: Outpoint([0u8; 36]),
+                satoshis: 2180,
+                contract: contract.clone(),
+                token: hex::decode(token).map_err(|e| abi_error("token", token, e))?,
+                script: Script::from_hex(script).map_err(|e| abi_error("script", script, e))?,
+                value: None,
+            })
+        })
+        .collect()
 }
 
-pub async fn get_uto(outpoint: String) -> Result<UTO> {
-    let metasync_api = MetasyncApi::new(constants::urls::METASYNC_URL.to_string());
+pub async fn get_uto(outpoint: String, config: &SdkConfig) -> Result<UTO> {
+    let metasync_api = MetasyncApi::new(config.metasync_url.clone());
     let metasync_uto = metasync_api.uto(&outpoint).await?;
 
     let uto = UTO {
@@ -1,5 +1,5 @@
 use crate::{
-    constants, get_mint_utos, get_uto, BitcoinFilesApi, Networks, SigilABI, SigilABIMethods,
+    get_mint_utos, get_uto, BitcoinFilesApi, SigilABI, SigilABIMethods, TwetchError,
     TwetchPayAction, TwetchPayCall, TxBuilder, TxBuilderOutput, Wallet,
 };
 use anyhow::Result;
@@ -44,20 +44,23 @@ impl SigilAction {
                     let contract = brc721::BRC721Basic {
                         store: Box::new(store),
                     };
-                    let uto = get_uto(params[0].clone().into()).await?;
-                    contract.abi(brc721::basic::ABI::Transfer(uto, params[1].clone().into()))?
+                    let uto = get_uto(params[0].clone().try_into()?, wallet.config()).await?;
+                    contract.abi(brc721::basic::ABI::Transfer(
+                        uto,
+                        params[1].clone().try_into()?,
+                    ))?
                 }
                 SigilABIMethods::Mint => {
                     let contract = brc721::BRC721Basic {
                         store: Box::new(store),
                     };
 
-                    let utos = get_mint_utos(params[0].clone(), abi.contract.clone());
+                    let utos = get_mint_utos(params[0].clone(), abi.contract.clone())?;
 
                     let utxo = UTXO {
-                        outpoint: params[1].clone().into(),
-                        satoshis: params[2].clone().into(),
-                        script: params[3].clone().into(),
+                        outpoint: params[1].clone().try_into()?,
+                        satoshis: params[2].clone().try_into()?,
+                        script: params[3].clone().try_into()?,
                     };
 
                     contract.abi(brc721::basic::ABI::Mint(utxo, utos))?
@@ -66,11 +69,11 @@ impl SigilAction {
                     let contract = brc721::BRC721Basic {
                         store: Box::new(store),
                     };
-                    let uto = get_uto(params[0].clone().into()).await?;
+                    let uto = get_uto(params[0].clone().try_into()?, wallet.config()).await?;
 
-                    let satoshis: u64 = params[2].clone().into();
+                    let satoshis: u64 = params[2].clone().try_into()?;
 
-                    let bfs = BitcoinFilesApi::new(constants::urls::DOGEFILES_URL.to_string());
+                    let bfs = BitcoinFilesApi::new(wallet.config().dogefiles_url.clone());
                     let contract_init = bfs.contract(&abi.contract).await?;
 
                     if let Some(royalty_percentage) = &contract_init.royalty_percentage {
@@ -93,7 +96,7 @@ impl SigilAction {
                     contract.abi(brc721::basic::ABI::Purchase(
                         uto,
                         wallet.account_locking_script()?,
-                        params[1].clone().into(),
+                        params[1].clone().try_into()?,
                         satoshis,
                     ))?
                 }
@@ -102,11 +105,11 @@ impl SigilAction {
                     let contract = brc721::BRC721Basic {
                         store: Box::new(store),
                     };
-                    let uto = get_uto(params[0].clone().into()).await?;
+                    let uto = get_uto(params[0].clone().try_into()?, wallet.config()).await?;
                     contract.abi(brc721::basic::ABI::Escrow(
                         uto,
-                        params[1].clone().into(),
-                        params[2].clone().into(),
+                        params[1].clone().try_into()?,
+                        params[2].clone().try_into()?,
                     ))?
                 }
                 SigilABIMethods::Vax => {
@@ -115,9 +118,9 @@ impl SigilAction {
                         store: Box::new(store),
                     };
 
-                    let frog = get_uto(params[0].clone().into()).await?;
-                    let vax = get_uto(params[1].clone().into()).await?;
-                    let apu = get_uto(params[2].clone().into()).await?;
+                    let frog = get_uto(params[0].clone().try_into()?, wallet.config()).await?;
+                    let vax = get_uto(params[1].clone().try_into()?, wallet.config()).await?;
+                    let apu = get_uto(params[2].clone().try_into()?, wallet.config()).await?;
 
                     contract.abi(lizervaxx::ABI::Vax(frog, vax, apu)).await?
                 }
@@ -127,8 +130,8 @@ impl SigilAction {
                         store: Box::new(store),
                     };
 
-                    let ape = get_uto(params[0].clone().into()).await?;
-                    let slurp = get_uto(params[1].clone().into()).await?;
+                    let ape = get_uto(params[0].clone().try_into()?, wallet.config()).await?;
+                    let slurp = get_uto(params[1].clone().try_into()?, wallet.config()).await?;
 
                     contract.abi(slurp_juice::ABI::Slurp(ape, slurp)).await?
                 }
@@ -141,12 +144,11 @@ impl SigilAction {
             let built_tx = wallet
                 .build_tx(&TxBuilder {
                     auto_fund,
-                    change_address: None,
                     contract: Some(abi.contract),
-                    extended_tx: None,
                     typed_signing: Some(typed_signing),
-                    network: Networks::BSV,
+                    network: call.network.clone(),
                     outputs,
+                    ..Default::default()
                 })
                 .await?;
 
@@ -158,6 +160,6 @@ impl SigilAction {
             });
         }
 
-        anyhow::bail!("invalid args");
+        Err(TwetchError::Abi("sigil call is missing args".to_string()).into())
     }
 }
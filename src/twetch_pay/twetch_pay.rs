@@ -1,9 +1,11 @@
 use crate::{
-    BuiltTx, Networks, Payee, PublishParams, SigilAction, TxBuilderOutput, V1TwetchAction, Wallet,
+    BuiltTx, Networks, Payee, PublishParams, SigilAction, Signer, TwetchError, TxBuilderOutput,
+    V1TwetchAction, Wallet,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::sync::Arc;
 
 pub struct TwetchPay {
     pub wallet: Wallet,
@@ -53,6 +55,12 @@ pub struct TwetchPayAction {
 impl TwetchPayAction {}
 
 impl TwetchPay {
+    pub fn from_signer(signer: Arc<dyn Signer>, token: String) -> Result<TwetchPay> {
+        Ok(TwetchPay {
+            wallet: Wallet::from_signer_and_token(signer, token)?,
+        })
+    }
+
     pub async fn run(&self, call: &TwetchPayCall) -> Result<TwetchPayAction> {
         match call.action_type {
             TwetchPayActionType::Twetch => Ok(V1TwetchAction::run(&self.wallet, call).await?),
@@ -60,7 +68,37 @@ impl TwetchPay {
         }
     }
 
+    /// Publishes the action, committing the utxo reservation of its transaction
+    /// when it went through and releasing it when it was turned down. When the
+    /// outcome is unknown, such as after a timeout, the reservation is left to
+    /// run out since the transaction may have been broadcast. Broadcast
+    /// transactions are recorded in the wallet's utxo store.
     pub async fn submit(&self, action: &TwetchPayAction) -> Result<PublishParams> {
-        Ok(V1TwetchAction::submit(&self.wallet, action).await?)
+        let res = V1TwetchAction::submit(&self.wallet, action).await;
+
+        if let Some(id) = &action.built_tx.reservation {
+            match &res {
+                Ok(_) => self.wallet.reservations().commit(id)?,
+                Err(e) if TwetchPay::is_rejection(e) => self.wallet.reservations().release(id)?,
+                Err(_) => {}
+            }
+        }
+
+        if res.is_ok() {
+            self.wallet.record_tx(&action.built_tx)?;
+        }
+
+        res
+    }
+
+    /// Whether a failed submit was turned down rather than lost on the way.
+    pub fn is_rejection(err: &anyhow::Error) -> bool {
+        match TwetchError::from_error(err) {
+            Some(TwetchError::Api { .. }) | Some(TwetchError::Auth(_)) => true,
+            Some(TwetchError::Http {
+                status: Some(v), ..
+            }) => (400..500).contains(&v),
+            _ => false,
+        }
     }
 }
@@ -1,4 +1,4 @@
-use crate::ABIv1Schema;
+use crate::{ABIv1Schema, TwetchError};
 use bsv::Hash;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -75,7 +75,11 @@ impl ABIv1 {
         let action = match schema.actions.get(action) {
             Some(v) => v.clone(),
             None => {
-                anyhow::bail!("ABI Error: action not found in abi shcema")
+                return Err(TwetchError::Abi(format!(
+                    "action '{}' not found in abi schema",
+                    action
+                ))
+                .into())
             }
         };
 
@@ -94,24 +98,30 @@ impl ABIv1 {
             .iter()
             .map(|e| {
                 if let Some(v) = object.get(&e.name) {
-                    return v.as_str().unwrap().to_string();
+                    return match v.as_str() {
+                        Some(v) => Ok(v.to_string()),
+                        None => Err(TwetchError::Abi(format!(
+                            "arg '{}' must be a string",
+                            e.name
+                        ))),
+                    };
                 }
 
                 if let Some(v) = &e.value {
-                    return v.to_string();
+                    return Ok(v.to_string());
                 }
 
                 if let Some(v) = &e.replace_value {
-                    return v.to_string();
+                    return Ok(v.to_string());
                 }
 
                 if let Some(v) = &e.default_value {
-                    return v.to_string();
+                    return Ok(v.to_string());
                 }
 
-                "".to_string()
+                Ok("".to_string())
             })
-            .collect();
+            .collect::<Result<Vec<String>, TwetchError>>()?;
 
         Ok(abi)
     }
@@ -124,7 +134,7 @@ impl ABIv1 {
             .find(|e| ABIv1ArgType::Signature == e.arg_type)
         {
             Some(v) => v,
-            None => anyhow::bail!("ABI Error: signature not found"),
+            None => return Err(TwetchError::Abi("signature not found".to_string()).into()),
         };
 
         let start_index = match arg.message_start_index {
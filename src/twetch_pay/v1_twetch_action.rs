@@ -1,12 +1,23 @@
 use crate::{
-    constants, ABIv1, Api, MetasyncApi, Networks, PayParams, Payee, PaymentDestination, PolynymApi,
-    PublishParams, TwetchPayAction, TwetchPayCall, TxBuilder, TxBuilderOutput, Wallet,
+    ABIv1, Api, BroadcastStatus, MetasyncApi, Networks, PayParams, Payee, PaymentDestination,
+    PolynymApi, PublishParams, TwetchError, TwetchPayAction, TwetchPayCall, TxBuilder,
+    TxBuilderOutput, Wallet,
 };
 use anyhow::Result;
 use bsv::{P2PKHAddress, Transaction};
 
 pub struct V1TwetchAction {}
 impl V1TwetchAction {
+    fn api(wallet: &Wallet) -> Result<Api> {
+        let token = match &wallet.token {
+            Some(v) => v.clone(),
+            None => return Err(TwetchError::Auth("wallet has no twetch token".to_string()).into()),
+        };
+
+        Ok(Api::new(wallet.config().api_url.clone(), token)
+            .with_client_identifier(wallet.config().client_identifier.clone()))
+    }
+
     pub async fn run(wallet: &Wallet, call: &TwetchPayCall) -> Result<TwetchPayAction> {
         let mut outputs: Vec<TxBuilderOutput> = Vec::new();
         let mut change_address: Option<P2PKHAddress> = None;
@@ -16,12 +27,12 @@ impl V1TwetchAction {
         if let Some(action) = &call.action {
             if let Some(args) = &call.args {
                 let mut abi = ABIv1::from_object(action, args)?;
-                let api = Api::new(
-                    constants::API_URL.to_string(),
-                    (&wallet.token.clone().unwrap()).to_string(),
-                );
+                let api = V1TwetchAction::api(wallet)?;
 
                 let response = api.payees(action, &abi.args).await?;
+                if !response.errors.is_empty() {
+                    return Err(TwetchError::api("payees", response.errors).into());
+                }
 
                 payees.append(&mut response.payees.clone());
 
@@ -39,7 +50,7 @@ impl V1TwetchAction {
 
                 abi.replace("#{invoice}".to_string(), response.invoice)?;
 
-                let signature = wallet.sign_message(abi.content_hash()?)?;
+                let signature = wallet.sign_message(abi.content_hash()?).await?;
 
                 abi.replace("#{mySignature}".to_string(), signature)?;
                 abi.replace(
@@ -68,7 +79,7 @@ impl V1TwetchAction {
                         })
                     }
 
-                    if payee.amount.is_string() && payee.amount.as_str().unwrap() == "change" {
+                    if payee.amount.as_str() == Some("change") {
                         change_address = Some(P2PKHAddress::from_string(&payee.to)?);
                     }
                 }
@@ -83,11 +94,9 @@ impl V1TwetchAction {
             .build_tx(&TxBuilder {
                 auto_fund: true,
                 change_address,
-                contract: None,
-                extended_tx: None,
-                typed_signing: None,
                 network: call.network.clone(),
                 outputs,
+                ..Default::default()
             })
             .await?;
 
@@ -104,7 +113,7 @@ impl V1TwetchAction {
         payment_destination: &PaymentDestination,
         wallet: &Wallet,
     ) -> Result<()> {
-        let api = PolynymApi::new(constants::POLYNYM_URL.to_string());
+        let api = PolynymApi::new(wallet.config().polynym_url.clone());
         api.submit_p2p_payment(tx, payment_destination, wallet)
             .await?;
         Ok(())
@@ -128,10 +137,7 @@ impl V1TwetchAction {
         };
 
         if let Some(action_name) = &action.call.action {
-            let api = Api::new(
-                constants::API_URL.to_string(),
-                (&wallet.token.clone().unwrap()).to_string(),
-            );
+            let api = V1TwetchAction::api(wallet)?;
 
             let mut pay_params = action.call.pay_params.clone();
 
@@ -158,7 +164,7 @@ impl V1TwetchAction {
 
             if let Some(errors) = response.errors {
                 if errors.len() > 0 {
-                    anyhow::bail!(format!("Publish Error: {}", errors.join(" ")))
+                    return Err(TwetchError::api("publish", errors).into());
                 }
             }
 
@@ -169,9 +175,14 @@ impl V1TwetchAction {
                 None => {}
             }
         } else {
-            let api = MetasyncApi::new(constants::METASYNC_URL.to_string());
-            api.broadcast(&action.built_tx.tx, &action.call.network, wallet)
+            let api = MetasyncApi::new(wallet.config().metasync_url.clone());
+            let res = api
+                .broadcast(&action.built_tx.tx, &action.call.network, wallet)
                 .await?;
+
+            if let BroadcastStatus::Rejected(v) = res.status() {
+                return Err(TwetchError::api("metasync", vec![v]).into());
+            }
         }
 
         Ok(publish_params)
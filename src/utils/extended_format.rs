@@ -0,0 +1,149 @@
+use crate::{BuiltTx, TwetchError, UTXO};
+use anyhow::Result;
+use bsv::Transaction;
+
+/// Extended Format (BRC-30) transactions carry the satoshis and locking script
+/// of each output they spend, so ARC can validate them without a lookup.
+pub struct ExtendedFormat {}
+
+const MARKER: [u8; 6] = [0, 0, 0, 0, 0, 0xef];
+
+impl ExtendedFormat {
+    /// `prevouts` holds the satoshis and locking script spent by each input, in order.
+    pub fn from_raw(raw_tx: &[u8], prevouts: &[(u64, Vec<u8>)]) -> Result<Vec<u8>> {
+        let mut reader = Reader {
+            raw: raw_tx,
+            pos: 0,
+        };
+        let mut ef = reader.read(4)?.to_vec();
+        ef.extend_from_slice(&MARKER);
+
+        let ninputs = reader.read_varint()?;
+        anyhow::ensure!(
+            ninputs as usize == prevouts.len(),
+            "EF Error: {} inputs but {} prevouts",
+            ninputs,
+            prevouts.len()
+        );
+        write_varint(&mut ef, ninputs);
+
+        for (satoshis, script) in prevouts {
+            let start = reader.pos;
+            reader.read(36)?;
+            // the unlocking script and the sequence
+            let script_len = reader.read_varint()?;
+            let len = match usize::try_from(script_len)
+                .ok()
+                .and_then(|e| e.checked_add(4))
+            {
+                Some(v) => v,
+                None => anyhow::bail!("EF Error: script length {} is out of range", script_len),
+            };
+            reader.read(len)?;
+            ef.extend_from_slice(&raw_tx[start..reader.pos]);
+
+            ef.extend_from_slice(&satoshis.to_le_bytes());
+            write_varint(&mut ef, script.len() as u64);
+            ef.extend_from_slice(script);
+        }
+
+        // outputs and locktime are the same as in the raw format
+        ef.extend_from_slice(&raw_tx[reader.pos..]);
+
+        Ok(ef)
+    }
+
+    /// Prevouts come from `utxos` by input index, or from the inputs themselves.
+    /// Signing records the locking script and satoshis of the wallet's inputs,
+    /// builds only the satoshis of `extended_tx` inputs, whose locking script
+    /// has to come with the extended transaction.
+    pub fn from_tx(tx: &Transaction, utxos: &[Option<UTXO>]) -> Result<Vec<u8>> {
+        let mut prevouts = Vec::new();
+
+        for i in 0..tx.get_ninputs() {
+            let input = tx.get_input(i);
+            let utxo = utxos.get(i).cloned().flatten();
+
+            let satoshis = match (&utxo, input.as_ref().and_then(|e| e.get_satoshis())) {
+                (Some(v), _) => Some(v.satoshis),
+                (None, v) => v,
+            };
+            let script = match (
+                utxo.and_then(|e| e.script),
+                input.and_then(|e| e.get_locking_script()),
+            ) {
+                (Some(v), _) | (None, Some(v)) => Some(v.to_bytes()),
+                (None, None) => None,
+            };
+
+            match (satoshis, script) {
+                (Some(satoshis), Some(script)) => prevouts.push((satoshis, script)),
+                _ => {
+                    return Err(TwetchError::Resolution(format!(
+                        "extended format is missing the prevout of input {}",
+                        i
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        ExtendedFormat::from_raw(&tx.to_bytes()?, &prevouts)
+    }
+
+    pub fn from_built_tx(built: &BuiltTx) -> Result<Vec<u8>> {
+        ExtendedFormat::from_tx(&built.tx, &built.utxos)
+    }
+
+    pub fn is_extended(raw_tx: &[u8]) -> bool {
+        raw_tx.len() > 10 && raw_tx[4..10] == MARKER
+    }
+}
+
+struct Reader<'a> {
+    raw: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = match self.pos.checked_add(n) {
+            Some(v) if v <= self.raw.len() => v,
+            _ => anyhow::bail!("EF Error: transaction ends at byte {}", self.raw.len()),
+        };
+        let bytes = &self.raw[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let size = match self.read(1)?[0] {
+            0xfd => 2,
+            0xfe => 4,
+            0xff => 8,
+            v => return Ok(v as u64),
+        };
+
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(self.read(size)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x10000..=0xffffffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod outpoint;
 pub use outpoint::*;
+
+pub mod extended_format;
+pub use extended_format::*;
@@ -1,8 +1,37 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-pub struct Outpoint {}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Outpoint {
+    pub txid: String,
+    pub vout: u32,
+}
 
 impl Outpoint {
+    pub fn new(txid: &str, vout: u32) -> Outpoint {
+        Outpoint {
+            txid: txid.to_string(),
+            vout,
+        }
+    }
+
+    /// Parses `txid:vout`.
+    pub fn from_string(outpoint: &str) -> Result<Outpoint> {
+        match outpoint.split_once(':') {
+            Some((txid, vout)) => Ok(Outpoint::new(txid, vout.parse::<u32>()?)),
+            None => anyhow::bail!("invalid outpoint '{}', expected txid:vout", outpoint),
+        }
+    }
+
+    /// Little endian txid followed by the vout, as utxo detective expects it.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(hex::decode(Outpoint::encode(
+            self.txid.clone(),
+            self.vout,
+        )?)?)
+    }
+
     pub fn encode(txid: String, vout: u32) -> Result<String> {
         let mut buf = Vec::new();
         let mut txid = hex::decode(txid)?;
@@ -24,3 +53,9 @@ impl Outpoint {
         Ok((hex::encode(txid), vout))
     }
 }
+
+impl fmt::Display for Outpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{Outpoint, UTXO};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Balance {
+    /// Mined utxos, ones a provider reports without a block height count as unconfirmed.
+    pub confirmed: u64,
+    pub unconfirmed: u64,
+    /// What a build can spend, without frozen utxos and token outputs.
+    pub spendable: u64,
+    pub frozen: u64,
+    /// 1 sat outputs, most likely carrying tokens, left out of `spendable`.
+    pub token_outputs: u64,
+    /// Satoshis by `UTXO.path`, -1 being the account address.
+    pub paths: BTreeMap<i32, u64>,
+}
+
+impl Balance {
+    pub fn from_utxos(utxos: &[UTXO], is_frozen: impl Fn(&Outpoint) -> bool) -> Balance {
+        let mut balance = Balance::default();
+
+        for utxo in utxos {
+            match utxo.height {
+                Some(_) => balance.confirmed += utxo.satoshis,
+                None => balance.unconfirmed += utxo.satoshis,
+            }
+
+            *balance.paths.entry(utxo.path).or_insert(0) += utxo.satoshis;
+
+            if is_frozen(&utxo.outpoint()) {
+                balance.frozen += utxo.satoshis;
+            } else if utxo.is_token_output() {
+                balance.token_outputs += 1;
+            } else {
+                balance.spendable += utxo.satoshis;
+            }
+        }
+
+        balance
+    }
+
+    pub fn total(&self) -> u64 {
+        self.confirmed + self.unconfirmed
+    }
+}
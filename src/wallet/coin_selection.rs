@@ -0,0 +1,213 @@
+use crate::{constants, TwetchError, UTXO};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Fee the transaction pays once the given utxos are added as inputs.
+pub type FeeFn<'a> = dyn Fn(&[UTXO]) -> Result<u64> + 'a;
+
+/// Picks the utxos that fund a transaction. `target` is what the inputs must
+/// cover before fees, `fee` prices the transaction for a candidate selection
+/// so strategies can account for the inputs they add.
+pub trait CoinSelector: Send + Sync {
+    fn select(&self, available: &[UTXO], target: u64, fee: &FeeFn) -> Result<Vec<UTXO>>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoinSelection {
+    LargestFirst,
+    /// Looks for a selection that needs no change output, falling back to largest-first.
+    BranchAndBound,
+    /// Confirmed utxos by block height, unconfirmed ones last.
+    OldestFirst,
+    /// A single utxo when one covers the payment, otherwise largest-first.
+    MinimiseInputs,
+}
+
+impl Default for CoinSelection {
+    fn default() -> CoinSelection {
+        CoinSelection::BranchAndBound
+    }
+}
+
+impl CoinSelector for CoinSelection {
+    fn select(&self, available: &[UTXO], target: u64, fee: &FeeFn) -> Result<Vec<UTXO>> {
+        match self {
+            CoinSelection::LargestFirst => LargestFirst.select(available, target, fee),
+            CoinSelection::BranchAndBound => {
+                BranchAndBound::default().select(available, target, fee)
+            }
+            CoinSelection::OldestFirst => OldestFirst.select(available, target, fee),
+            CoinSelection::MinimiseInputs => MinimiseInputs.select(available, target, fee),
+        }
+    }
+}
+
+/// Adds utxos in order until they cover the target and the fee for the inputs so far.
+fn accumulate(ordered: Vec<UTXO>, target: u64, fee: &FeeFn) -> Result<Vec<UTXO>> {
+    let mut selected: Vec<UTXO> = Vec::new();
+    let mut selected_sats = 0_u64;
+    let mut ordered = ordered.into_iter();
+
+    while selected_sats < target + fee(&selected)? {
+        match ordered.next() {
+            Some(utxo) => {
+                selected_sats += utxo.satoshis;
+                selected.push(utxo);
+            }
+            None => {
+                return Err(TwetchError::InsufficientFunds {
+                    required: target + fee(&selected)?,
+                    available: selected_sats,
+                }
+                .into())
+            }
+        }
+    }
+
+    Ok(selected)
+}
+
+pub struct LargestFirst;
+
+impl CoinSelector for LargestFirst {
+    fn select(&self, available: &[UTXO], target: u64, fee: &FeeFn) -> Result<Vec<UTXO>> {
+        let mut ordered = available.to_vec();
+        ordered.sort_by(|a, b| b.satoshis.cmp(&a.satoshis));
+        accumulate(ordered, target, fee)
+    }
+}
+
+pub struct OldestFirst;
+
+impl CoinSelector for OldestFirst {
+    fn select(&self, available: &[UTXO], target: u64, fee: &FeeFn) -> Result<Vec<UTXO>> {
+        let mut ordered = available.to_vec();
+        ordered.sort_by_key(|e| e.height.unwrap_or(i64::MAX));
+        accumulate(ordered, target, fee)
+    }
+}
+
+pub struct MinimiseInputs;
+
+impl CoinSelector for MinimiseInputs {
+    fn select(&self, available: &[UTXO], target: u64, fee: &FeeFn) -> Result<Vec<UTXO>> {
+        if target + fee(&[])? == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut ordered = available.to_vec();
+        ordered.sort_by_key(|e| e.satoshis);
+
+        for utxo in &ordered {
+            let single = vec![utxo.clone()];
+            if utxo.satoshis >= target + fee(&single)? {
+                return Ok(single);
+            }
+        }
+
+        LargestFirst.select(available, target, fee)
+    }
+}
+
+/// Depth-first search for a selection whose value lands between the target
+/// and the target plus `cost_of_change`, so the transaction needs no change
+/// output. Anything over the target goes to the miner.
+pub struct BranchAndBound {
+    pub cost_of_change: u64,
+    pub max_tries: usize,
+    pub fallback: Box<dyn CoinSelector>,
+}
+
+impl Default for BranchAndBound {
+    fn default() -> BranchAndBound {
+        BranchAndBound {
+            cost_of_change: constants::MIN_CHANGE_SATS,
+            max_tries: 100000,
+            fallback: Box::new(LargestFirst),
+        }
+    }
+}
+
+impl BranchAndBound {
+    fn search(&self, values: &[(UTXO, u64)], target: u64) -> Option<Vec<usize>> {
+        let upper = target + self.cost_of_change;
+        let mut remaining: u64 = values.iter().map(|e| e.1).sum();
+        let mut selection: Vec<bool> = Vec::new();
+        let mut value = 0_u64;
+        let mut best: Option<(u64, Vec<bool>)> = None;
+
+        for _ in 0..self.max_tries {
+            let backtrack = if value + remaining < target || value > upper {
+                true
+            } else if value >= target {
+                let waste = value - target;
+                if best.as_ref().map_or(true, |(w, _)| waste < *w) {
+                    best = Some((waste, selection.clone()));
+                }
+                true
+            } else {
+                false
+            };
+
+            if backtrack {
+                // undo trailing exclusions, then exclude the last included utxo
+                while let Some(false) = selection.last() {
+                    selection.pop();
+                    remaining += values[selection.len()].1;
+                }
+
+                match selection.last_mut() {
+                    Some(v) => {
+                        *v = false;
+                        value -= values[selection.len() - 1].1;
+                    }
+                    None => break,
+                }
+            } else {
+                if selection.len() == values.len() {
+                    break;
+                }
+                remaining -= values[selection.len()].1;
+                value += values[selection.len()].1;
+                selection.push(true);
+            }
+        }
+
+        best.map(|(_, selection)| {
+            selection
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| **v)
+                .map(|(i, _)| i)
+                .collect()
+        })
+    }
+}
+
+impl CoinSelector for BranchAndBound {
+    fn select(&self, available: &[UTXO], target: u64, fee: &FeeFn) -> Result<Vec<UTXO>> {
+        // value of each utxo net of what it costs to spend
+        let base_fee = fee(&[])?;
+        let mut values: Vec<(UTXO, u64)> = Vec::new();
+        for utxo in available {
+            let input_fee = fee(&[utxo.clone()])?.saturating_sub(base_fee);
+            if utxo.satoshis > input_fee {
+                values.push((utxo.clone(), utxo.satoshis - input_fee));
+            }
+        }
+        values.sort_by(|a, b| b.1.cmp(&a.1));
+
+        if let Some(indexes) = self.search(&values, target + base_fee) {
+            let selected: Vec<UTXO> = indexes.iter().map(|i| values[*i].0.clone()).collect();
+            let selected_sats: u64 = selected.iter().map(|e| e.satoshis).sum();
+
+            // per input fees are rounded, so check the match against the real fee
+            if selected_sats >= target + fee(&selected)? {
+                return Ok(selected);
+            }
+        }
+
+        self.fallback.select(available, target, fee)
+    }
+}
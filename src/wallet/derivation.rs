@@ -0,0 +1,53 @@
+use crate::Networks;
+use serde::{Deserialize, Serialize};
+
+/// HD paths a `Wallet` derives its keys from.
+///
+/// The default policy is account 0 on mainnet, which are the paths twetch
+/// wallets have always used: `m/0/0`, `m/44'/0'/0'/0` and `m/86'/0'/0'/0`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DerivationPolicy {
+    pub account_path: String,
+    pub wallet_path: String,
+    pub taproot_path: String,
+}
+
+impl Default for DerivationPolicy {
+    fn default() -> DerivationPolicy {
+        DerivationPolicy::new(&Networks::BSV, 0)
+    }
+}
+
+impl DerivationPolicy {
+    pub fn coin_type(network: &Networks) -> u32 {
+        match network {
+            Networks::BSV => 0,
+            Networks::TBSV => 1,
+        }
+    }
+
+    /// The account key path has no coin type level, so only the paymail wallet
+    /// and taproot paths change between networks.
+    pub fn new(network: &Networks, account: u32) -> DerivationPolicy {
+        let coin_type = DerivationPolicy::coin_type(network);
+
+        DerivationPolicy {
+            account_path: format!("m/0/{}", account),
+            wallet_path: format!("m/44'/{}'/{}'/0", coin_type, account),
+            taproot_path: format!("m/86'/{}'/{}'/0", coin_type, account),
+        }
+    }
+
+    /// Arbitrary paths, for wallets imported from other software.
+    pub fn custom(
+        account_path: String,
+        wallet_path: String,
+        taproot_path: String,
+    ) -> DerivationPolicy {
+        DerivationPolicy {
+            account_path,
+            wallet_path,
+            taproot_path,
+        }
+    }
+}
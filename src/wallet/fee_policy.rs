@@ -0,0 +1,105 @@
+use crate::{constants, MapiApi};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// How `TxBuilder` prices a transaction. Data rates apply to the bytes of
+/// OP_RETURN outputs, standard rates to everything else.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeePolicy {
+    /// Sats per byte.
+    Rate { standard: f64, data: f64 },
+    /// A total fee in sats, whatever the size of the transaction.
+    TotalFee(u64),
+    /// Rates from the fee quote of a mAPI miner, fetched on every build.
+    Mapi {
+        url: String,
+        token: String,
+        /// Public key the miner signs its fee quotes with. Without it any
+        /// signed quote is accepted, whoever signed it.
+        #[serde(default)]
+        miner_id: Option<String>,
+    },
+}
+
+impl Default for FeePolicy {
+    fn default() -> FeePolicy {
+        FeePolicy::Rate {
+            standard: constants::TX_FEE_RATE,
+            data: constants::TX_FEE_RATE,
+        }
+    }
+}
+
+impl FeePolicy {
+    pub fn rate(rate: f64) -> FeePolicy {
+        FeePolicy::Rate {
+            standard: rate,
+            data: rate,
+        }
+    }
+
+    pub async fn resolve(&self) -> Result<FeeRates> {
+        match self {
+            FeePolicy::Rate { standard, data } => Ok(FeeRates {
+                standard: *standard,
+                data: *data,
+                total: None,
+            }),
+            FeePolicy::TotalFee(v) => Ok(FeeRates {
+                standard: constants::TX_FEE_RATE,
+                data: constants::TX_FEE_RATE,
+                total: Some(*v),
+            }),
+            FeePolicy::Mapi {
+                url,
+                token,
+                miner_id,
+            } => {
+                let mut api = MapiApi::new(url.clone(), token.clone());
+                if let Some(v) = miner_id {
+                    api = api.with_miner_id(v.clone());
+                }
+                let quote = api.fee_quote().await?;
+
+                let standard = match quote.rate("standard") {
+                    Some(v) => v,
+                    None => anyhow::bail!("Mapi Error: fee quote has no standard fee"),
+                };
+
+                Ok(FeeRates {
+                    standard,
+                    data: quote.rate("data").unwrap_or(standard),
+                    total: None,
+                })
+            }
+        }
+    }
+}
+
+/// A `FeePolicy` resolved for one build.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeRates {
+    pub standard: f64,
+    pub data: f64,
+    pub total: Option<u64>,
+}
+
+impl FeeRates {
+    pub fn fee(&self, standard_bytes: usize, data_bytes: usize) -> u64 {
+        match self.total {
+            Some(v) => v,
+            None => (standard_bytes as f64 * self.standard + data_bytes as f64 * self.data).ceil()
+                as u64,
+        }
+    }
+
+    /// Lowest effective rate a build may end up with. Rates set explicitly
+    /// below `MIN_TX_FEE_RATE` are honoured.
+    pub fn min_rate(&self) -> f64 {
+        match self.total {
+            Some(_) => constants::MIN_TX_FEE_RATE,
+            None => self.standard.min(self.data).min(constants::MIN_TX_FEE_RATE),
+        }
+    }
+}
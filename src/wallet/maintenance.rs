@@ -0,0 +1,49 @@
+use crate::{constants, TxBuilder, UTXO};
+use anyhow::Result;
+use bsv::Transaction;
+
+/// Groups utxos, smallest first, into consolidation transactions of at most
+/// `max_size` bytes each. 1 sat outputs are left alone since they most likely
+/// carry tokens, as are utxos that would end up in a group of their own.
+pub fn plan_consolidation(utxos: &[UTXO], max_size: usize) -> Result<Vec<Vec<UTXO>>> {
+    let mut candidates: Vec<UTXO> = utxos
+        .iter()
+        .filter(|e| !e.is_token_output())
+        .cloned()
+        .collect();
+    candidates.sort_by_key(|e| e.satoshis);
+
+    let tx = Transaction::default();
+    let mut batches: Vec<Vec<UTXO>> = Vec::new();
+    let mut batch: Vec<Option<UTXO>> = Vec::new();
+
+    for utxo in candidates {
+        batch.push(Some(utxo));
+
+        let size = TxBuilder::estimate_size(&tx, &batch, constants::P2PKH_OUTPUT_SIZE)?;
+        if batch.len() > 1 && size > max_size {
+            let next = batch.pop().unwrap();
+            batches.push(batch.into_iter().flatten().collect());
+            batch = vec![next];
+        }
+    }
+    batches.push(batch.into_iter().flatten().collect());
+
+    Ok(batches.into_iter().filter(|e| e.len() > 1).collect())
+}
+
+/// Satoshis of each of the `outputs` equal outputs `satoshis` split into
+/// once `fee` is paid. What doesn't divide evenly is left to the miner.
+pub fn plan_split(satoshis: u64, outputs: usize, fee: u64) -> Result<Vec<u64>> {
+    anyhow::ensure!(outputs > 1, "a split needs at least 2 outputs");
+
+    let amount = satoshis.saturating_sub(fee) / outputs as u64;
+    anyhow::ensure!(
+        amount >= 2 * constants::MIN_CHANGE_SATS,
+        "{} sats are too few to split in {} outputs",
+        satoshis,
+        outputs
+    );
+
+    Ok(vec![amount; outputs])
+}
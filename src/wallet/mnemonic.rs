@@ -0,0 +1,73 @@
+use bip39::{Language, Mnemonic};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MnemonicError {
+    WordCount(usize),
+    UnknownWord { index: usize, word: String },
+    Checksum,
+    Invalid(String),
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::WordCount(v) => write!(
+                f,
+                "Mnemonic Error: expected 12, 15, 18, 21 or 24 words, got {}",
+                v
+            ),
+            MnemonicError::UnknownWord { index, word } => write!(
+                f,
+                "Mnemonic Error: word {} '{}' is not in the wordlist",
+                index + 1,
+                word
+            ),
+            MnemonicError::Checksum => write!(f, "Mnemonic Error: invalid checksum"),
+            MnemonicError::Invalid(v) => write!(f, "Mnemonic Error: {}", v),
+        }
+    }
+}
+
+impl std::error::Error for MnemonicError {}
+
+/// Lower-cases the phrase and collapses whitespace the way users tend to mistype it.
+pub fn normalize_mnemonic(phrase: &str) -> String {
+    phrase
+        .split_whitespace()
+        .map(|e| e.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Checks a phrase against the BIP39 english wordlist and checksum, returning it normalized.
+pub fn validate_mnemonic(phrase: &str) -> Result<String, MnemonicError> {
+    let normalized = normalize_mnemonic(phrase);
+
+    match Mnemonic::parse_in_normalized(Language::English, &normalized) {
+        Ok(v) => Ok(v.to_string()),
+        Err(bip39::Error::BadWordCount(v)) => Err(MnemonicError::WordCount(v)),
+        Err(bip39::Error::UnknownWord(index)) => Err(MnemonicError::UnknownWord {
+            index,
+            word: normalized
+                .split(' ')
+                .nth(index)
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        Err(bip39::Error::InvalidChecksum) => Err(MnemonicError::Checksum),
+        Err(e) => Err(MnemonicError::Invalid(format!("{}", e))),
+    }
+}
+
+/// Generates a fresh english mnemonic of 12 or 24 words.
+pub fn generate_mnemonic(word_count: usize) -> Result<String, MnemonicError> {
+    if word_count != 12 && word_count != 24 {
+        return Err(MnemonicError::WordCount(word_count));
+    }
+
+    match Mnemonic::generate_in(Language::English, word_count) {
+        Ok(v) => Ok(v.to_string()),
+        Err(e) => Err(MnemonicError::Invalid(format!("{}", e))),
+    }
+}
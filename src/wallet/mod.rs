@@ -1,12 +1,36 @@
+pub mod balance;
+pub mod coin_selection;
+pub mod derivation;
+pub mod fee_policy;
+pub mod maintenance;
+pub mod mnemonic;
 pub mod networks;
+pub mod recipient;
+pub mod reservation;
+pub mod signer;
 pub mod tx_builder;
 pub mod utxo;
-
+pub mod utxo_provider;
+pub mod utxo_store;
+
+pub use balance::*;
+pub use coin_selection::*;
+pub use derivation::*;
+pub use fee_policy::*;
+pub use maintenance::*;
+pub use mnemonic::*;
 pub use networks::*;
+pub use recipient::*;
+pub use reservation::*;
+pub use signer::*;
 pub use tx_builder::*;
 pub use utxo::*;
+pub use utxo_provider::*;
+pub use utxo_store::*;
 
-use crate::{constants, AuthToken, MetasyncApi};
+use crate::{
+    constants, AuthToken, MetasyncApi, Outpoint, SdkConfig, TwetchError, UtxoDetectiveApi,
+};
 use anyhow::Result;
 //use bitcoin::{
 //network::constants::Network as BTCNetwork,
@@ -14,13 +38,26 @@ use anyhow::Result;
 //};
 use bsv::{
     ChainParams, ExtendedPrivateKey, ExtendedPublicKey, P2PKHAddress, PrivateKey, PublicKey,
-    Script, SigHash, Transaction, BSM, ECIES,
+    Script, Transaction, TxOut, ECIES,
+};
+use sigil_types::TypedSigning;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
 };
-use sigil_types::{TypedSigner, TypedSigning};
 
 #[derive(Clone)]
 pub struct Wallet {
-    seed: String,
+    signer: Arc<dyn Signer>,
+    change_index: Arc<AtomicU32>,
+    frozen: Arc<Mutex<HashSet<Outpoint>>>,
+    reservations: Arc<UtxoReservations>,
+    utxo_store: Option<Arc<dyn UtxoStore>>,
+    utxo_provider: Arc<dyn UtxoProvider>,
+    config: SdkConfig,
     pub user_id: Option<String>,
     pub token: Option<String>,
 }
@@ -32,44 +69,120 @@ pub struct EphemeralCipher {
 
 impl Wallet {
     pub fn new(seed: String) -> Wallet {
+        Wallet::from_signer(Arc::new(MnemonicSigner::new(seed)))
+    }
+
+    /// Validates the phrase against the BIP39 wordlist and checksum before
+    /// building the wallet, with an optional BIP39 passphrase.
+    pub fn from_mnemonic(phrase: &str, passphrase: Option<String>) -> Result<Wallet> {
+        let seed = validate_mnemonic(phrase)?;
+        Ok(Wallet::from_signer(Arc::new(
+            MnemonicSigner::with_passphrase(seed, passphrase),
+        )))
+    }
+
+    pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+        Ok(generate_mnemonic(word_count)?)
+    }
+
+    pub fn validate_mnemonic(phrase: &str) -> Result<(), MnemonicError> {
+        validate_mnemonic(phrase)?;
+        Ok(())
+    }
+
+    pub fn from_signer(signer: Arc<dyn Signer>) -> Wallet {
         Wallet {
-            seed,
+            signer,
+            change_index: Arc::new(AtomicU32::new(0)),
+            frozen: Default::default(),
+            reservations: Default::default(),
+            utxo_store: None,
+            utxo_provider: Arc::new(NetworkUtxoProvider::default()),
+            config: SdkConfig::current(),
             user_id: None,
             token: None,
         }
     }
 
+    /// Wallet that can look up addresses, balances and UTXOs and build unsigned
+    /// transactions, see `Wallet::sign_built_tx`. Pass `wallet_xpub()` of the
+    /// seed wallet to also derive its paymail path keys.
+    pub fn watch_only(
+        account_public_key: PublicKey,
+        wallet_xpub: Option<ExtendedPublicKey>,
+    ) -> Wallet {
+        Wallet::from_signer(Arc::new(WatchOnlySigner::new(
+            account_public_key,
+            wallet_xpub,
+        )))
+    }
+
     pub fn from_seed_and_token(seed: String, token: String) -> Result<Wallet> {
+        Wallet::from_signer_and_token(Arc::new(MnemonicSigner::new(seed)), token)
+    }
+
+    pub fn from_signer_and_token(signer: Arc<dyn Signer>, token: String) -> Result<Wallet> {
         let auth_token = AuthToken::new(token.clone())?;
 
         Ok(Wallet {
-            seed,
+            signer,
+            change_index: Arc::new(AtomicU32::new(0)),
+            frozen: Default::default(),
+            reservations: Default::default(),
+            utxo_store: None,
+            utxo_provider: Arc::new(NetworkUtxoProvider::default()),
+            config: SdkConfig::current(),
             user_id: Some(auth_token.user_id),
             token: Some(token),
         })
     }
 
+    pub fn signer(&self) -> Arc<dyn Signer> {
+        self.signer.clone()
+    }
+
+    fn mnemonic(&self) -> Result<&MnemonicSigner> {
+        match self.signer.mnemonic() {
+            Some(v) => Ok(v),
+            None => {
+                Err(TwetchError::Signing("signer does not expose private keys".to_string()).into())
+            }
+        }
+    }
+
+    pub fn derivation_policy(&self) -> DerivationPolicy {
+        self.signer.derivation_policy()
+    }
+
+    /// Same mnemonic and passphrase, deriving its keys from other paths.
+    pub fn with_derivation_policy(&self, policy: DerivationPolicy) -> Result<Wallet> {
+        Ok(Wallet {
+            signer: Arc::new(self.mnemonic()?.with_derivation_policy(policy)),
+            change_index: Arc::new(AtomicU32::new(0)),
+            frozen: self.frozen.clone(),
+            reservations: self.reservations.clone(),
+            utxo_store: self.utxo_store.clone(),
+            utxo_provider: self.utxo_provider.clone(),
+            config: self.config.clone(),
+            user_id: self.user_id.clone(),
+            token: self.token.clone(),
+        })
+    }
+
     pub fn xpriv(&self) -> Result<ExtendedPrivateKey> {
-        Ok(ExtendedPrivateKey::from_mnemonic(
-            self.seed.as_bytes(),
-            None,
-        )?)
+        self.mnemonic()?.xpriv()
     }
 
     pub fn xpub(&self) -> Result<ExtendedPublicKey> {
         Ok(ExtendedPublicKey::from_xpriv(&self.xpriv()?))
     }
 
-    fn account_xpriv(&self) -> Result<ExtendedPrivateKey> {
-        Ok(self.xpriv()?.derive_from_path("m/0/0")?)
-    }
-
     pub fn account_private_key(&self) -> Result<PrivateKey> {
-        Ok(self.account_xpriv()?.get_private_key())
+        self.mnemonic()?.account_private_key()
     }
 
     pub fn account_public_key(&self) -> Result<PublicKey> {
-        Ok(self.account_private_key()?.to_public_key()?)
+        self.signer.account_public_key()
     }
 
     pub fn account_address(&self) -> Result<P2PKHAddress> {
@@ -81,7 +194,9 @@ impl Wallet {
     }
 
     pub async fn account_utxos(&self, network: &Networks) -> Result<Vec<UTXO>> {
-        Ok(UTXO::from_woc(&self.account_address()?, network).await?)
+        WhatsOnChainProvider::new(self.config.whatsonchain_url.clone())
+            .address_utxos(&self.account_address()?, network)
+            .await
     }
 
     pub async fn account_balance(&self, network: &Networks) -> Result<u64> {
@@ -94,15 +209,90 @@ impl Wallet {
     }
 
     pub fn wallet_xpriv(&self) -> Result<ExtendedPrivateKey> {
-        Ok(self.xpriv()?.derive_from_path("m/44'/0'/0'/0")?)
+        self.mnemonic()?.wallet_xpriv()
     }
 
     pub fn wallet_xpub(&self) -> Result<ExtendedPublicKey> {
-        Ok(ExtendedPublicKey::from_xpriv(&self.wallet_xpriv()?))
+        self.signer.wallet_xpub()
+    }
+
+    /// Next unused change index, change key `n` is derived at
+    /// `CHANGE_PATH_START + n` under the wallet xpub. The index moves past the
+    /// change paths of every utxo the wallet sees and of its `UtxoStore`, so
+    /// it resumes after a restart from the change the store recorded.
+    pub fn change_index(&self) -> u32 {
+        self.change_index.load(Ordering::SeqCst)
+    }
+
+    pub fn set_change_index(&self, index: u32) {
+        self.change_index.store(index, Ordering::SeqCst)
+    }
+
+    /// Moves the change index past the change paths among `utxos`.
+    pub fn observe_change_paths(&self, utxos: &[UTXO]) {
+        let last = utxos
+            .iter()
+            .filter(|e| e.path >= constants::CHANGE_PATH_START)
+            .map(|e| (e.path - constants::CHANGE_PATH_START) as u32)
+            .max();
+
+        if let Some(v) = last {
+            self.change_index.fetch_max(v + 1, Ordering::SeqCst);
+        }
+    }
+
+    /// Derives a fresh change key under the wallet xpub and returns its address
+    /// with the utxo path that spends it. No provider indexes those keys, so
+    /// only wallets with a `UtxoStore` to record the change get them, others
+    /// send change to the account address (path -1), as do wallets without a
+    /// wallet xpub.
+    pub fn next_change_address(&self) -> Result<(P2PKHAddress, i32)> {
+        let store = match &self.utxo_store {
+            Some(v) => v,
+            None => return Ok((self.account_address()?, -1)),
+        };
+
+        let wallet_xpub = match self.wallet_xpub() {
+            Ok(v) => v,
+            Err(_) => return Ok((self.account_address()?, -1)),
+        };
+
+        let stored: Vec<UTXO> = store.all()?.into_iter().map(|e| e.utxo).collect();
+        self.observe_change_paths(&stored);
+
+        let index = self.change_index.fetch_add(1, Ordering::SeqCst);
+        let path = constants::CHANGE_PATH_START + index as i32;
+        let address = wallet_xpub
+            .derive(path as u32)?
+            .get_public_key()
+            .to_p2pkh_address()?;
+
+        Ok((address, path))
+    }
+
+    /// Hands the change key at `path` back when it is still the last one
+    /// handed out, for builds that fail after deriving their change.
+    pub fn release_change_path(&self, path: i32) {
+        if path < constants::CHANGE_PATH_START {
+            return;
+        }
+
+        let index = (path - constants::CHANGE_PATH_START) as u32;
+        let _ = self.change_index.compare_exchange(
+            index + 1,
+            index,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+    }
+
+    pub fn next_change_script(&self) -> Result<(Script, i32)> {
+        let (address, path) = self.next_change_address()?;
+        Ok((address.get_locking_script()?, path))
     }
 
     pub fn taproot_xpriv(&self) -> Result<ExtendedPrivateKey> {
-        Ok(self.xpriv()?.derive_from_path("m/86'/0'/0'/0")?)
+        self.mnemonic()?.taproot_xpriv()
     }
 
     pub fn taproot_xpub(&self) -> Result<ExtendedPublicKey> {
@@ -126,7 +316,13 @@ impl Wallet {
     //}
 
     pub async fn wallet_utxos(&self, network: &Networks) -> Result<Vec<UTXO>> {
-        Ok(UTXO::from_metasync(&self.account_public_key()?, network).await?)
+        MetasyncProvider::new(self.config.metasync_url.clone())
+            .utxos(&UtxoQuery {
+                account_public_key: self.account_public_key()?,
+                network: network.clone(),
+                amount: 0,
+            })
+            .await
     }
 
     pub fn ephemeral_encrypt(&self, plain_text: Vec<u8>) -> Result<EphemeralCipher> {
@@ -146,95 +342,184 @@ impl Wallet {
         Ok(EphemeralCipher { cipher_text, hash })
     }
 
-    //pub async fn balance(&self, network: &Networks) -> Result<u64> {
-    //Ok(self.utxos(network).await?.iter().map(|e| e.satoshis).sum())
-    //}
+    /// Every utxo the wallet's provider and store know of, plus those of the
+    /// account address from WhatsOnChain. Builds only fund from the account
+    /// address when the wallet's utxo provider returns it. On BSV the block
+    /// heights of paymail path utxos come from utxo detective, on other
+    /// networks those without one count as unconfirmed.
+    pub async fn balance(&self, network: &Networks) -> Result<Balance> {
+        let query = UtxoQuery {
+            account_public_key: self.account_public_key()?,
+            network: network.clone(),
+            amount: u64::MAX,
+        };
+        let provider = CompositeProvider::new(vec![
+            self.utxo_provider.clone(),
+            Arc::new(WhatsOnChainProvider::new(
+                self.config.whatsonchain_url.clone(),
+            )),
+        ]);
+        let utxos = provider.utxos(&query).await?;
+
+        let mut utxos = match &self.utxo_store {
+            Some(v) => v.merge(utxos)?,
+            None => utxos,
+        };
 
-    pub async fn utxos(&self, network: &Networks, amount: u64) -> Result<Vec<UTXO>> {
-        match network {
-            Networks::BSV => {
-                Ok(UTXO::from_utxo_detective(&self.account_public_key()?, amount).await?)
-            }
-            _ => {
-                let wallet_utxos = self.wallet_utxos(network);
-                let account_utxos = self.account_utxos(network);
+        if let (Networks::BSV, Ok(wallet_xpub)) = (network, self.wallet_xpub()) {
+            UtxoDetectiveProvider::new(self.config.utxo_detective_url.clone())
+                .fill_heights(&mut utxos, &wallet_xpub)
+                .await?;
+        }
 
-                let mut wallet_utxos = wallet_utxos.await?;
-                let mut account_utxos = account_utxos.await?;
+        Ok(Balance::from_utxos(&utxos, |e| self.is_frozen(e)))
+    }
 
-                wallet_utxos.append(&mut account_utxos);
+    pub fn reservations(&self) -> Arc<UtxoReservations> {
+        self.reservations.clone()
+    }
 
-                Ok(wallet_utxos)
-            }
+    /// Shares reservations between wallets, or persists them with
+    /// `UtxoReservations::with_store`.
+    pub fn with_reservations(&self, reservations: Arc<UtxoReservations>) -> Wallet {
+        Wallet {
+            reservations,
+            ..self.clone()
         }
     }
 
-    pub fn sign_message(&self, message: String) -> Result<String> {
-        let private_key = self.account_xpriv()?.get_private_key();
-        let sig = BSM::sign_message(&private_key, message.as_bytes())?.to_compact_bytes(None);
-        Ok(base64::encode_config(sig, base64::STANDARD))
+    pub fn config(&self) -> &SdkConfig {
+        &self.config
     }
 
-    pub fn sign_typed(&self, typed_signing: &mut TypedSigning) -> Result<TypedSigning> {
-        let mut typed_signers = Vec::new();
-        for _ in &typed_signing.signatures {
-            typed_signers.push(TypedSigner {
-                target: self.account_locking_script()?.to_bytes(),
-                pub_key: Some(self.account_public_key()?),
-                priv_key: Some(self.account_private_key()?),
-                r: None,
-                k: None,
-            });
+    /// Network of the wallet's config, for calls that don't name one.
+    pub fn network(&self) -> Networks {
+        self.config.network.clone()
+    }
+
+    /// Talks to the endpoints of `config`, looking up utxos with its services.
+    /// Set a custom provider afterwards, this one replaces it.
+    pub fn with_config(&self, config: SdkConfig) -> Wallet {
+        Wallet {
+            utxo_provider: Arc::new(NetworkUtxoProvider::new(config.clone())),
+            config,
+            ..self.clone()
         }
-        Ok(typed_signing.sign_all(typed_signers)?)
-    }
-
-    pub fn sign_transaction(&self, tx: &mut Transaction, utxos: &Vec<Option<UTXO>>) -> Result<()> {
-        let xpriv_wallet = self.wallet_xpriv()?;
-        let private_key_account = self.account_private_key()?;
-
-        for i in 0..tx.get_ninputs() {
-            if let Some(utxo) = &utxos[i] {
-                let mut input = match tx.get_input(i) {
-                    Some(v) => v,
-                    None => continue,
-                };
-
-                let private_key = if utxo.path == -1 {
-                    private_key_account.clone()
-                } else {
-                    xpriv_wallet.derive(utxo.path as u32)?.get_private_key()
-                };
-
-                // P2PKH
-                let signature = tx.sign(
-                    &private_key,
-                    SigHash::InputsOutputs,
-                    i,
-                    &private_key
-                        .to_public_key()?
-                        .to_p2pkh_address()?
-                        .get_locking_script()?,
-                    utxo.satoshis,
-                )?;
-
-                let asm = format!(
-                    "{} {}",
-                    signature.to_hex()?,
-                    private_key.to_public_key()?.to_hex()?
-                );
-                input.set_locking_script(
-                    &private_key
-                        .to_public_key()?
-                        .to_p2pkh_address()?
-                        .get_locking_script()?,
-                );
-                input.set_unlocking_script(&Script::from_asm_string(&asm)?);
-                tx.set_input(i, &input)
-            }
+    }
+
+    pub fn utxo_provider(&self) -> Arc<dyn UtxoProvider> {
+        self.utxo_provider.clone()
+    }
+
+    /// Looks up utxos with `provider` instead of the default services of each network.
+    pub fn with_utxo_provider(&self, provider: Arc<dyn UtxoProvider>) -> Wallet {
+        Wallet {
+            utxo_provider: provider,
+            ..self.clone()
         }
+    }
 
-        Ok(())
+    pub fn utxo_store(&self) -> Option<Arc<dyn UtxoStore>> {
+        self.utxo_store.clone()
+    }
+
+    /// Keeps track of the utxos of transactions recorded with `record_tx`,
+    /// merging them into what remote services return.
+    pub fn with_utxo_store(&self, store: Arc<dyn UtxoStore>) -> Wallet {
+        Wallet {
+            utxo_store: Some(store),
+            ..self.clone()
+        }
+    }
+
+    /// Call once a built transaction is broadcast, `TwetchPay::submit` does.
+    pub fn record_tx(&self, built: &BuiltTx) -> Result<()> {
+        match &self.utxo_store {
+            Some(v) => v.record(built),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn reconcile_utxos(&self) -> Result<()> {
+        let store = match &self.utxo_store {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        let utxo_detective = UtxoDetectiveApi::new(self.config.utxo_detective_url.clone());
+        reconcile_utxo_store(
+            store.as_ref(),
+            &utxo_detective,
+            Some(self.account_address()?.to_string()?),
+        )
+        .await
+    }
+
+    /// Frozen utxos are never spent by `utxos`, auto funding or sweeps, only
+    /// when picked explicitly through `TxBuilder.coin_control`. The freeze list
+    /// is shared with clones and `with_derivation_policy` wallets but only kept
+    /// in memory, restore it with `freeze` after a restart.
+    pub fn freeze(&self, outpoint: Outpoint) {
+        self.frozen.lock().unwrap().insert(outpoint);
+    }
+
+    pub fn unfreeze(&self, outpoint: &Outpoint) {
+        self.frozen.lock().unwrap().remove(outpoint);
+    }
+
+    pub fn is_frozen(&self, outpoint: &Outpoint) -> bool {
+        self.frozen.lock().unwrap().contains(outpoint)
+    }
+
+    pub fn frozen(&self) -> Vec<Outpoint> {
+        self.frozen.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Spendable utxos, leaving out frozen ones and token outputs.
+    pub async fn utxos(&self, network: &Networks, amount: u64) -> Result<Vec<UTXO>> {
+        Ok(self
+            .all_utxos(network, amount)
+            .await?
+            .into_iter()
+            .filter(|e| !self.is_frozen(&e.outpoint()) && !e.is_token_output())
+            .collect())
+    }
+
+    /// Every utxo of the wallet, frozen ones included.
+    pub async fn all_utxos(&self, network: &Networks, amount: u64) -> Result<Vec<UTXO>> {
+        let utxos = self.remote_utxos(network, amount).await?;
+
+        match &self.utxo_store {
+            Some(v) => v.merge(utxos),
+            None => Ok(utxos),
+        }
+    }
+
+    async fn remote_utxos(&self, network: &Networks, amount: u64) -> Result<Vec<UTXO>> {
+        let query = UtxoQuery {
+            account_public_key: self.account_public_key()?,
+            network: network.clone(),
+            amount,
+        };
+
+        self.utxo_provider.utxos(&query).await
+    }
+
+    pub async fn sign_message(&self, message: String) -> Result<String> {
+        let sig = self.signer.sign_message(message.as_bytes()).await?;
+        Ok(base64::encode_config(sig, base64::STANDARD))
+    }
+
+    pub async fn sign_typed(&self, typed_signing: &TypedSigning) -> Result<TypedSigning> {
+        self.signer.sign_typed(typed_signing).await
+    }
+
+    pub async fn sign_transaction(
+        &self,
+        tx: &mut Transaction,
+        utxos: &Vec<Option<UTXO>>,
+    ) -> Result<()> {
+        self.signer.sign_transaction(tx, utxos).await
     }
 
     pub async fn resolve_output(
@@ -247,4 +532,184 @@ impl Wallet {
     pub async fn build_tx(&self, builder: &TxBuilder) -> Result<BuiltTx> {
         TxBuilder::build(builder, self).await
     }
+
+    /// Moves every utxo of a foreign key, or of a key at an old derivation path
+    /// of this wallet, to the next change address of this wallet. The swept
+    /// utxos are reserved on this wallet and its output is listed in
+    /// `BuiltTx.wallet_outputs`.
+    pub async fn sweep(&self, source: &SweepSource, network: &Networks) -> Result<BuiltTx> {
+        let private_key = match source {
+            SweepSource::Wif(v) => PrivateKey::from_wif(v)?,
+            SweepSource::Path(v) => self.xpriv()?.derive_from_path(v)?.get_private_key(),
+        };
+
+        let source_wallet = Wallet::from_signer(Arc::new(KeySigner::new(private_key)))
+            .with_config(self.config.clone())
+            .with_reservations(self.reservations());
+        let utxos = source_wallet.account_utxos(network).await?;
+        anyhow::ensure!(!utxos.is_empty(), "nothing to sweep");
+
+        let (address, path) = self.next_change_address()?;
+
+        let res = source_wallet
+            .build_tx(&TxBuilder {
+                network: network.clone(),
+                utxos: Some(utxos),
+                sweep_to: Some(address.to_string()?),
+                ..Default::default()
+            })
+            .await;
+
+        let mut built = match res {
+            Ok(v) => v,
+            Err(e) => {
+                self.release_change_path(path);
+                return Err(e);
+            }
+        };
+        built.wallet_outputs = vec![WalletOutput { vout: 0, path }];
+
+        Ok(built)
+    }
+
+    /// Merges the wallet's unfrozen utxos into one output to the wallet's next
+    /// change address per transaction of at most `max_size` bytes, see
+    /// `plan_consolidation` and `next_change_address`.
+    /// When a transaction fails to build, those built before it are dropped
+    /// and their reservations released.
+    pub async fn consolidate(
+        &self,
+        network: &Networks,
+        max_size: usize,
+        fee_policy: &FeePolicy,
+    ) -> Result<Vec<BuiltTx>> {
+        let utxos = self.utxos(network, u64::MAX).await?;
+
+        let mut built: Vec<BuiltTx> = Vec::new();
+        for batch in plan_consolidation(&utxos, max_size)? {
+            let res = self
+                .build_tx(&TxBuilder {
+                    network: network.clone(),
+                    auto_fund: true,
+                    fee_policy: fee_policy.clone(),
+                    coin_control: batch.iter().map(|e| e.outpoint()).collect(),
+                    utxos: Some(utxos.clone()),
+                    ..Default::default()
+                })
+                .await;
+
+            match res {
+                Ok(v) => built.push(v),
+                Err(e) => {
+                    for id in built.iter().filter_map(|e| e.reservation.as_ref()) {
+                        self.reservations.release(id)?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(built)
+    }
+
+    /// Fans `outpoint` out into `outputs` equal outputs to the wallet's next
+    /// change addresses, so that many transactions can be chained off it in
+    /// parallel. The fee comes out before dividing, see `plan_split`, and the
+    /// outputs are listed in `BuiltTx.wallet_outputs`.
+    pub async fn split(
+        &self,
+        outpoint: &Outpoint,
+        outputs: usize,
+        network: &Networks,
+        fee_policy: &FeePolicy,
+    ) -> Result<BuiltTx> {
+        let utxos = self.utxos(network, u64::MAX).await?;
+        let utxo = match utxos.iter().find(|e| e.outpoint() == *outpoint) {
+            Some(v) => v,
+            None => anyhow::bail!("utxo {} not found in the wallet", outpoint),
+        };
+        let mut destinations = Vec::new();
+        for _ in 0..outputs {
+            destinations.push(self.next_change_address()?);
+        }
+
+        let res = self
+            .build_split(utxo, &utxos, &destinations, network, fee_policy)
+            .await;
+
+        // hand the change keys back, last one first
+        if res.is_err() {
+            for (_, path) in destinations.iter().rev() {
+                self.release_change_path(*path);
+            }
+        }
+
+        res
+    }
+
+    async fn build_split(
+        &self,
+        utxo: &UTXO,
+        utxos: &[UTXO],
+        destinations: &[(P2PKHAddress, i32)],
+        network: &Networks,
+        fee_policy: &FeePolicy,
+    ) -> Result<BuiltTx> {
+        let rates = fee_policy.resolve().await?;
+
+        // priced like TxBuilder prices a build, leaving room for change
+        let mut tx = Transaction::default();
+        for (address, _) in destinations {
+            tx.add_output(&TxOut::new(0, &address.get_locking_script()?));
+        }
+        let fee = TxBuilder::estimate_fee(
+            &tx,
+            &vec![Some(utxo.clone())],
+            constants::P2PKH_OUTPUT_SIZE,
+            &rates,
+        )?;
+
+        let amounts = plan_split(utxo.satoshis, destinations.len(), fee)?;
+
+        let mut tx_outputs = Vec::new();
+        let mut wallet_outputs = Vec::new();
+        for (i, ((address, path), sats)) in destinations.iter().zip(amounts).enumerate() {
+            tx_outputs.push(TxBuilderOutput {
+                sats,
+                address: Some(address.to_string()?),
+                to: None,
+                script: None,
+                args: None,
+                encrypt_args: None,
+            });
+            wallet_outputs.push(WalletOutput {
+                vout: i as u32,
+                path: *path,
+            });
+        }
+
+        let mut built = self
+            .build_tx(&TxBuilder {
+                network: network.clone(),
+                outputs: tx_outputs,
+                auto_fund: true,
+                fee_policy: fee_policy.clone(),
+                coin_control: vec![utxo.outpoint()],
+                utxos: Some(utxos.to_vec()),
+                ..Default::default()
+            })
+            .await?;
+        built.wallet_outputs = wallet_outputs;
+
+        Ok(built)
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        !self.signer.can_sign()
+    }
+
+    /// Signs a transaction built by a watch-only wallet holding the same keys.
+    pub async fn sign_built_tx(&self, built: &BuiltTx) -> Result<BuiltTx> {
+        TxBuilder::sign(built, self).await
+    }
 }
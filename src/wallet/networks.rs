@@ -1,11 +1,18 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Networks {
     BSV,
     TBSV,
 }
 
+/// Network of `SdkConfig::current`, so builders left at the default follow the config.
+impl Default for Networks {
+    fn default() -> Networks {
+        crate::SdkConfig::current().network
+    }
+}
+
 impl From<String> for Networks {
     fn from(v: String) -> Networks {
         match v.as_str() {
@@ -0,0 +1,104 @@
+use bsv::P2PKHAddress;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The `to` of a `TxBuilderOutput`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Recipient {
+    Address(String),
+    Paymail(String),
+    /// Twetch user id, written `@123` or `123`.
+    UserId(String),
+    /// HandCash handle, written `$handle`.
+    Handle(String),
+    /// RelayX handle, written `1handle`. Anything shaped like an address is
+    /// parsed as one, failing on a bad checksum.
+    OneHandle(String),
+}
+
+impl Recipient {
+    pub fn parse(to: &str) -> Result<Recipient, RecipientError> {
+        let to = to.trim();
+
+        if P2PKHAddress::from_string(to).is_ok() {
+            return Ok(Recipient::Address(to.to_string()));
+        }
+
+        if to.contains('@') && !to.starts_with('@') {
+            return Ok(Recipient::Paymail(to.to_string()));
+        }
+
+        let user_id = to.strip_prefix('@').unwrap_or(to);
+        if !user_id.is_empty() && user_id.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Recipient::UserId(user_id.to_string()));
+        }
+
+        if let Some(v) = to.strip_prefix('$').filter(|e| !e.is_empty()) {
+            return Ok(Recipient::Handle(v.to_string()));
+        }
+
+        // a mistyped address must not be looked up as someone's 1handle
+        if Recipient::is_address_shaped(to) {
+            return Err(RecipientError::new(to, "invalid address checksum"));
+        }
+
+        if let Some(v) = to.strip_prefix('1').filter(|e| !e.is_empty()) {
+            return Ok(Recipient::OneHandle(v.to_string()));
+        }
+
+        Err(RecipientError::new(
+            to,
+            "not an address, paymail, user id or handle",
+        ))
+    }
+
+    /// 25 to 35 base58 characters, the length of a P2PKH address.
+    fn is_address_shaped(to: &str) -> bool {
+        const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+        (25..=35).contains(&to.len()) && to.chars().all(|c| BASE58.contains(c))
+    }
+
+    /// How polynym expects the recipient to be written.
+    pub fn polynym_query(&self) -> String {
+        format!("{}", self)
+    }
+}
+
+impl fmt::Display for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recipient::Address(v) | Recipient::Paymail(v) => write!(f, "{}", v),
+            Recipient::UserId(v) => write!(f, "@{}", v),
+            Recipient::Handle(v) => write!(f, "${}", v),
+            Recipient::OneHandle(v) => write!(f, "1{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipientError {
+    pub recipient: String,
+    pub reason: String,
+}
+
+impl RecipientError {
+    pub fn new(recipient: &str, reason: &str) -> RecipientError {
+        RecipientError {
+            recipient: recipient.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RecipientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Recipient Error: failed to resolve '{}', {}",
+            self.recipient, self.reason
+        )
+    }
+}
+
+impl std::error::Error for RecipientError {}
@@ -0,0 +1,231 @@
+use crate::Outpoint;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|e| e.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reservation {
+    pub id: String,
+    pub outpoint: Outpoint,
+    pub expires_at: u64,
+    /// Spent by a broadcast transaction, kept until indexers have caught up.
+    pub committed: bool,
+}
+
+/// Persists reservations across restarts. The store is read once and then
+/// overwritten on every change, so it can't be shared between processes.
+pub trait ReservationStore: Send + Sync {
+    fn load(&self) -> Result<Vec<Reservation>>;
+
+    fn save(&self, reservations: &Vec<Reservation>) -> Result<()>;
+}
+
+/// Reservations as a JSON file.
+pub struct FileReservationStore {
+    path: PathBuf,
+}
+
+impl FileReservationStore {
+    pub fn new(path: PathBuf) -> FileReservationStore {
+        FileReservationStore { path }
+    }
+}
+
+impl ReservationStore for FileReservationStore {
+    fn load(&self) -> Result<Vec<Reservation>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(serde_json::from_slice(&std::fs::read(&self.path)?)?)
+    }
+
+    fn save(&self, reservations: &Vec<Reservation>) -> Result<()> {
+        Ok(std::fs::write(
+            &self.path,
+            serde_json::to_vec(reservations)?,
+        )?)
+    }
+}
+
+/// Locks the utxos a build selected so concurrent builds of the same wallet
+/// don't spend them too. Reservations run out after `timeout_ms` unless they
+/// are committed or released first.
+pub struct UtxoReservations {
+    reservations: Mutex<HashMap<Outpoint, Reservation>>,
+    timeout_ms: u64,
+    store: Option<Box<dyn ReservationStore>>,
+}
+
+impl Default for UtxoReservations {
+    fn default() -> UtxoReservations {
+        UtxoReservations::new(crate::constants::UTXO_RESERVATION_TIMEOUT_MS)
+    }
+}
+
+impl UtxoReservations {
+    pub fn new(timeout_ms: u64) -> UtxoReservations {
+        UtxoReservations {
+            reservations: Mutex::new(HashMap::new()),
+            timeout_ms,
+            store: None,
+        }
+    }
+
+    pub fn with_store(
+        timeout_ms: u64,
+        store: Box<dyn ReservationStore>,
+    ) -> Result<UtxoReservations> {
+        let now = now_ms();
+        let reservations = store
+            .load()?
+            .into_iter()
+            .filter(|e| e.expires_at > now)
+            .map(|e| (e.outpoint.clone(), e))
+            .collect();
+
+        Ok(UtxoReservations {
+            reservations: Mutex::new(reservations),
+            timeout_ms,
+            store: Some(store),
+        })
+    }
+
+    /// Changes take effect once the store saved them.
+    fn update<T>(&self, f: impl FnOnce(&mut HashMap<Outpoint, Reservation>) -> T) -> Result<T> {
+        let mut reservations = self.reservations.lock().unwrap();
+
+        let now = now_ms();
+        let mut updated = reservations.clone();
+        updated.retain(|_, e| e.expires_at > now);
+
+        let value = f(&mut updated);
+
+        if let Some(store) = &self.store {
+            store.save(&updated.values().cloned().collect())?;
+        }
+        *reservations = updated;
+
+        Ok(value)
+    }
+
+    pub fn is_reserved(&self, outpoint: &Outpoint) -> bool {
+        let now = now_ms();
+        match self.reservations.lock().unwrap().get(outpoint) {
+            Some(v) => v.expires_at > now,
+            None => false,
+        }
+    }
+
+    /// Reserves every outpoint or none of them, returning the reservation id.
+    pub fn reserve(&self, outpoints: &[Outpoint]) -> Result<String> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes)?;
+        let id = hex::encode(bytes);
+        let expires_at = now_ms() + self.timeout_ms;
+
+        let taken = self.update(|reservations| {
+            let taken = outpoints.iter().find(|e| reservations.contains_key(e));
+
+            if taken.is_none() {
+                for outpoint in outpoints {
+                    reservations.insert(
+                        outpoint.clone(),
+                        Reservation {
+                            id: id.clone(),
+                            outpoint: outpoint.clone(),
+                            expires_at,
+                            committed: false,
+                        },
+                    );
+                }
+            }
+
+            taken.cloned()
+        })?;
+
+        match taken {
+            Some(v) => anyhow::bail!("utxo {} is reserved by another build", v),
+            None => Ok(id),
+        }
+    }
+
+    /// The transaction was broadcast, its utxos stay locked for another
+    /// timeout so stale indexers can't hand them out again.
+    pub fn commit(&self, id: &str) -> Result<()> {
+        let expires_at = now_ms() + self.timeout_ms;
+
+        self.update(|reservations| {
+            for e in reservations.values_mut().filter(|e| e.id == id) {
+                e.committed = true;
+                e.expires_at = expires_at;
+            }
+        })
+    }
+
+    /// The transaction was never broadcast, its utxos can be spent again.
+    pub fn release(&self, id: &str) -> Result<()> {
+        self.update(|reservations| reservations.retain(|_, e| e.id != id || e.committed))
+    }
+
+    pub fn reservations(&self) -> Vec<Reservation> {
+        let now = now_ms();
+        self.reservations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|e| e.expires_at > now)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Releases a reservation when dropped unless it was kept, so builds that
+/// fail halfway don't leave their utxos locked.
+pub struct ReservationGuard {
+    reservations: Arc<UtxoReservations>,
+    id: Option<String>,
+}
+
+impl ReservationGuard {
+    pub fn new(
+        reservations: Arc<UtxoReservations>,
+        outpoints: &[Outpoint],
+    ) -> Result<ReservationGuard> {
+        let id = reservations.reserve(outpoints)?;
+
+        Ok(ReservationGuard {
+            reservations,
+            id: Some(id),
+        })
+    }
+
+    pub fn keep(mut self) -> Option<String> {
+        self.id.take()
+    }
+}
+
+impl Drop for ReservationGuard {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            let _ = self.reservations.release(&id);
+        }
+    }
+}
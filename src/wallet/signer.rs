@@ -0,0 +1,585 @@
+use crate::{DerivationPolicy, HttpTransport, TwetchError, UTXO};
+use anyhow::Result;
+use async_trait::async_trait;
+use bsv::{
+    ExtendedPrivateKey, ExtendedPublicKey, PrivateKey, PublicKey, Script, SigHash, Transaction,
+    TxIn, BSM,
+};
+use serde::{Deserialize, Serialize};
+use sigil_types::{TypedSigner, TypedSigning};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// Holds the keys of a wallet and produces every signature the SDK needs.
+///
+/// `Wallet` only ever talks to its keys through this trait, so the keys can
+/// live in-process (`MnemonicSigner`) or in an isolated signing service
+/// (`RemoteSigner`).
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Signer: Send + Sync {
+    fn account_public_key(&self) -> Result<PublicKey>;
+
+    fn wallet_xpub(&self) -> Result<ExtendedPublicKey>;
+
+    /// Returns the compact bitcoin signed message signature of the account key.
+    async fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>>;
+
+    async fn sign_typed(&self, typed_signing: &TypedSigning) -> Result<TypedSigning>;
+
+    /// Signs every input that has a matching wallet `UTXO`, leaving the others untouched.
+    async fn sign_transaction(&self, tx: &mut Transaction, utxos: &Vec<Option<UTXO>>)
+        -> Result<()>;
+
+    fn derivation_policy(&self) -> DerivationPolicy {
+        DerivationPolicy::default()
+    }
+
+    /// Watch-only signers return false, transactions built with them are left unsigned.
+    fn can_sign(&self) -> bool {
+        true
+    }
+
+    /// Signers that hold the mnemonic in-process expose it for private key access.
+    fn mnemonic(&self) -> Option<&MnemonicSigner> {
+        None
+    }
+}
+
+/// Key material derived from the mnemonic. `from_mnemonic` runs 2048 rounds
+/// of PBKDF2, so every key is derived once and then shared by all clones.
+#[derive(Default)]
+struct KeyCache {
+    xpriv: OnceLock<ExtendedPrivateKey>,
+    account_xpriv: OnceLock<ExtendedPrivateKey>,
+    account_public_key: OnceLock<PublicKey>,
+    wallet_xpriv: OnceLock<ExtendedPrivateKey>,
+    taproot_xpriv: OnceLock<ExtendedPrivateKey>,
+    wallet_children: Mutex<HashMap<u32, PrivateKey>>,
+}
+
+fn cached<T: Clone>(cell: &OnceLock<T>, derive: impl FnOnce() -> Result<T>) -> Result<T> {
+    if let Some(v) = cell.get() {
+        return Ok(v.clone());
+    }
+
+    let v = derive()?;
+    Ok(cell.get_or_init(|| v).clone())
+}
+
+#[derive(Clone)]
+pub struct MnemonicSigner {
+    seed: String,
+    passphrase: Option<String>,
+    policy: DerivationPolicy,
+    keys: Arc<KeyCache>,
+}
+
+impl MnemonicSigner {
+    pub fn new(seed: String) -> MnemonicSigner {
+        MnemonicSigner::with_passphrase(seed, None)
+    }
+
+    /// Uses the optional BIP39 passphrase ("25th word") when deriving the seed.
+    pub fn with_passphrase(seed: String, passphrase: Option<String>) -> MnemonicSigner {
+        MnemonicSigner {
+            seed,
+            passphrase,
+            policy: DerivationPolicy::default(),
+            keys: Arc::new(KeyCache::default()),
+        }
+    }
+
+    pub fn with_derivation_policy(&self, policy: DerivationPolicy) -> MnemonicSigner {
+        MnemonicSigner {
+            seed: self.seed.clone(),
+            passphrase: self.passphrase.clone(),
+            policy,
+            keys: Arc::new(KeyCache::default()),
+        }
+    }
+
+    pub fn xpriv(&self) -> Result<ExtendedPrivateKey> {
+        cached(&self.keys.xpriv, || {
+            Ok(ExtendedPrivateKey::from_mnemonic(
+                self.seed.as_bytes(),
+                self.passphrase.as_ref().map(|e| e.as_bytes().to_vec()),
+            )?)
+        })
+    }
+
+    pub fn account_xpriv(&self) -> Result<ExtendedPrivateKey> {
+        cached(&self.keys.account_xpriv, || {
+            Ok(self.xpriv()?.derive_from_path(&self.policy.account_path)?)
+        })
+    }
+
+    pub fn account_private_key(&self) -> Result<PrivateKey> {
+        Ok(self.account_xpriv()?.get_private_key())
+    }
+
+    pub fn wallet_xpriv(&self) -> Result<ExtendedPrivateKey> {
+        cached(&self.keys.wallet_xpriv, || {
+            Ok(self.xpriv()?.derive_from_path(&self.policy.wallet_path)?)
+        })
+    }
+
+    /// Private key of a paymail path UTXO, `UTXO.path` under the wallet xpriv.
+    pub fn wallet_private_key(&self, path: u32) -> Result<PrivateKey> {
+        if let Ok(children) = self.keys.wallet_children.lock() {
+            if let Some(v) = children.get(&path) {
+                return Ok(v.clone());
+            }
+        }
+
+        let private_key = self.wallet_xpriv()?.derive(path)?.get_private_key();
+
+        if let Ok(mut children) = self.keys.wallet_children.lock() {
+            children.insert(path, private_key.clone());
+        }
+
+        Ok(private_key)
+    }
+
+    pub fn taproot_xpriv(&self) -> Result<ExtendedPrivateKey> {
+        cached(&self.keys.taproot_xpriv, || {
+            Ok(self.xpriv()?.derive_from_path(&self.policy.taproot_path)?)
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Signer for MnemonicSigner {
+    fn account_public_key(&self) -> Result<PublicKey> {
+        cached(&self.keys.account_public_key, || {
+            Ok(self.account_private_key()?.to_public_key()?)
+        })
+    }
+
+    fn wallet_xpub(&self) -> Result<ExtendedPublicKey> {
+        Ok(ExtendedPublicKey::from_xpriv(&self.wallet_xpriv()?))
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>> {
+        let private_key = self.account_private_key()?;
+        Ok(BSM::sign_message(&private_key, message)?.to_compact_bytes(None))
+    }
+
+    async fn sign_typed(&self, typed_signing: &TypedSigning) -> Result<TypedSigning> {
+        sign_typed_with_key(typed_signing, &self.account_private_key()?)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut Transaction,
+        utxos: &Vec<Option<UTXO>>,
+    ) -> Result<()> {
+        let private_key_account = self.account_private_key()?;
+
+        for i in 0..tx.get_ninputs() {
+            if let Some(Some(utxo)) = utxos.get(i) {
+                let mut input = match tx.get_input(i) {
+                    Some(v) => v,
+                    None => continue,
+                };
+
+                let private_key = if utxo.path == -1 {
+                    private_key_account.clone()
+                } else {
+                    self.wallet_private_key(utxo.path as u32)?
+                };
+
+                sign_p2pkh_input(tx, &mut input, i, &private_key, utxo.satoshis)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn derivation_policy(&self) -> DerivationPolicy {
+        self.policy.clone()
+    }
+
+    fn mnemonic(&self) -> Option<&MnemonicSigner> {
+        Some(self)
+    }
+}
+
+fn sign_typed_with_key(
+    typed_signing: &TypedSigning,
+    private_key: &PrivateKey,
+) -> Result<TypedSigning> {
+    let public_key = private_key.to_public_key()?;
+    let target = public_key
+        .to_p2pkh_address()?
+        .get_locking_script()?
+        .to_bytes();
+
+    let mut typed_signers = Vec::new();
+    for _ in &typed_signing.signatures {
+        typed_signers.push(TypedSigner {
+            target: target.clone(),
+            pub_key: Some(public_key.clone()),
+            priv_key: Some(private_key.clone()),
+            r: None,
+            k: None,
+        });
+    }
+
+    let mut typed_signing = typed_signing.clone();
+    Ok(typed_signing.sign_all(typed_signers)?)
+}
+
+fn sign_p2pkh_input(
+    tx: &mut Transaction,
+    input: &mut TxIn,
+    vin: usize,
+    private_key: &PrivateKey,
+    satoshis: u64,
+) -> Result<()> {
+    let locking_script = private_key
+        .to_public_key()?
+        .to_p2pkh_address()?
+        .get_locking_script()?;
+
+    let signature = tx.sign(
+        private_key,
+        SigHash::InputsOutputs,
+        vin,
+        &locking_script,
+        satoshis,
+    )?;
+
+    let asm = format!(
+        "{} {}",
+        signature.to_hex()?,
+        private_key.to_public_key()?.to_hex()?
+    );
+    // kept on the input for Extended Format broadcasts, see `ExtendedFormat::from_tx`
+    input.set_locking_script(&locking_script);
+    input.set_satoshis(satoshis);
+    input.set_unlocking_script(&Script::from_asm_string(&asm)?);
+    tx.set_input(vin, input);
+
+    Ok(())
+}
+
+/// A single private key, such as an imported WIF or a key from an old
+/// derivation path being swept into a wallet. Every utxo is signed with it.
+#[derive(Clone)]
+pub struct KeySigner {
+    private_key: PrivateKey,
+}
+
+impl KeySigner {
+    pub fn new(private_key: PrivateKey) -> KeySigner {
+        KeySigner { private_key }
+    }
+
+    pub fn from_wif(wif: &str) -> Result<KeySigner> {
+        Ok(KeySigner::new(PrivateKey::from_wif(wif)?))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Signer for KeySigner {
+    fn account_public_key(&self) -> Result<PublicKey> {
+        Ok(self.private_key.to_public_key()?)
+    }
+
+    fn wallet_xpub(&self) -> Result<ExtendedPublicKey> {
+        anyhow::bail!("Wallet Error: single key signer has no wallet xpub")
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>> {
+        Ok(BSM::sign_message(&self.private_key, message)?.to_compact_bytes(None))
+    }
+
+    async fn sign_typed(&self, typed_signing: &TypedSigning) -> Result<TypedSigning> {
+        sign_typed_with_key(typed_signing, &self.private_key)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut Transaction,
+        utxos: &Vec<Option<UTXO>>,
+    ) -> Result<()> {
+        for i in 0..tx.get_ninputs() {
+            if let (Some(Some(utxo)), Some(mut input)) = (utxos.get(i), tx.get_input(i)) {
+                sign_p2pkh_input(tx, &mut input, i, &self.private_key, utxo.satoshis)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Public keys only, for services that track a wallet without holding its seed.
+#[derive(Clone)]
+pub struct WatchOnlySigner {
+    account_public_key: PublicKey,
+    wallet_xpub: Option<ExtendedPublicKey>,
+}
+
+impl WatchOnlySigner {
+    pub fn new(
+        account_public_key: PublicKey,
+        wallet_xpub: Option<ExtendedPublicKey>,
+    ) -> WatchOnlySigner {
+        WatchOnlySigner {
+            account_public_key,
+            wallet_xpub,
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Signer for WatchOnlySigner {
+    fn account_public_key(&self) -> Result<PublicKey> {
+        Ok(self.account_public_key.clone())
+    }
+
+    fn wallet_xpub(&self) -> Result<ExtendedPublicKey> {
+        match &self.wallet_xpub {
+            Some(v) => Ok(v.clone()),
+            None => anyhow::bail!("Wallet Error: watch-only wallet has no wallet xpub"),
+        }
+    }
+
+    async fn sign_message(&self, _message: &[u8]) -> Result<Vec<u8>> {
+        Err(TwetchError::Signing("watch-only wallet cannot sign".to_string()).into())
+    }
+
+    async fn sign_typed(&self, _typed_signing: &TypedSigning) -> Result<TypedSigning> {
+        Err(TwetchError::Signing("watch-only wallet cannot sign".to_string()).into())
+    }
+
+    async fn sign_transaction(
+        &self,
+        _tx: &mut Transaction,
+        _utxos: &Vec<Option<UTXO>>,
+    ) -> Result<()> {
+        Err(TwetchError::Signing("watch-only wallet cannot sign".to_string()).into())
+    }
+
+    fn can_sign(&self) -> bool {
+        false
+    }
+}
+
+/// Request body understood by a signing service, see `SignerRequest::handle`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum SignerRequest {
+    Keys,
+    SignMessage {
+        message: String,
+    },
+    SignTyped {
+        typed_signing: TypedSigning,
+    },
+    SignTransaction {
+        tx: String,
+        utxos: Vec<Option<UTXO>>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SignerResponse {
+    pub account_public_key: Option<String>,
+    pub wallet_xpub: Option<String>,
+    pub signature: Option<String>,
+    pub typed_signing: Option<TypedSigning>,
+    pub tx: Option<String>,
+    pub error: Option<String>,
+}
+
+impl SignerRequest {
+    /// Serves a request with a local signer. This is all a signing service has
+    /// to run behind its HTTP endpoint to be usable with `RemoteSigner`.
+    pub async fn handle(self, signer: &dyn Signer) -> SignerResponse {
+        match self.respond(signer).await {
+            Ok(v) => v,
+            Err(e) => SignerResponse {
+                error: Some(format!("{}", e)),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn respond(self, signer: &dyn Signer) -> Result<SignerResponse> {
+        let response = match self {
+            SignerRequest::Keys => SignerResponse {
+                account_public_key: Some(signer.account_public_key()?.to_hex()?),
+                wallet_xpub: Some(signer.wallet_xpub()?.to_string()?),
+                ..Default::default()
+            },
+            SignerRequest::SignMessage { message } => SignerResponse {
+                signature: Some(hex::encode(
+                    signer.sign_message(&hex::decode(message)?).await?,
+                )),
+                ..Default::default()
+            },
+            SignerRequest::SignTyped { typed_signing } => SignerResponse {
+                typed_signing: Some(signer.sign_typed(&typed_signing).await?),
+                ..Default::default()
+            },
+            SignerRequest::SignTransaction { tx, utxos } => {
+                let mut tx = Transaction::from_compact_hex(&tx)?;
+                signer.sign_transaction(&mut tx, &utxos).await?;
+                SignerResponse {
+                    tx: Some(tx.to_compact_hex()?),
+                    ..Default::default()
+                }
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+/// Signer backed by a signing service speaking the `SignerRequest` protocol.
+pub struct RemoteSigner {
+    url: String,
+    token: Option<String>,
+    account_public_key: PublicKey,
+    wallet_xpub: ExtendedPublicKey,
+    transport: HttpTransport,
+}
+
+impl RemoteSigner {
+    /// Connects to the signing service and caches the public keys it holds.
+    pub async fn connect(url: String, token: Option<String>) -> Result<RemoteSigner> {
+        RemoteSigner::connect_with_transport(url, token, HttpTransport::shared()).await
+    }
+
+    /// Same as `connect`, sending every request through `transport`.
+    pub async fn connect_with_transport(
+        url: String,
+        token: Option<String>,
+        transport: HttpTransport,
+    ) -> Result<RemoteSigner> {
+        let keys = RemoteSigner::send(&transport, &url, &token, &SignerRequest::Keys).await?;
+
+        let account_public_key = match keys.account_public_key {
+            Some(v) => PublicKey::from_hex(&v)?,
+            None => anyhow::bail!("RemoteSigner Error: missing account public key"),
+        };
+
+        let wallet_xpub = match keys.wallet_xpub {
+            Some(v) => ExtendedPublicKey::from_string(&v)?,
+            None => anyhow::bail!("RemoteSigner Error: missing wallet xpub"),
+        };
+
+        Ok(RemoteSigner {
+            url,
+            token,
+            account_public_key,
+            wallet_xpub,
+            transport,
+        })
+    }
+
+    async fn send(
+        transport: &HttpTransport,
+        url: &String,
+        token: &Option<String>,
+        request: &SignerRequest,
+    ) -> Result<SignerResponse> {
+        let mut builder = transport.post(format!("{}/sign", url));
+
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let res = builder
+            .json(request)
+            .send_checked()
+            .await?
+            .json::<SignerResponse>()
+            .await?;
+
+        if let Some(error) = res.error {
+            return Err(TwetchError::Signing(format!("remote signer, {}", error)).into());
+        }
+
+        Ok(res)
+    }
+
+    pub async fn request(&self, request: &SignerRequest) -> Result<SignerResponse> {
+        RemoteSigner::send(&self.transport, &self.url, &self.token, request).await
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Signer for RemoteSigner {
+    fn account_public_key(&self) -> Result<PublicKey> {
+        Ok(self.account_public_key.clone())
+    }
+
+    fn wallet_xpub(&self) -> Result<ExtendedPublicKey> {
+        Ok(self.wallet_xpub.clone())
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>> {
+        let res = self
+            .request(&SignerRequest::SignMessage {
+                message: hex::encode(message),
+            })
+            .await?;
+
+        match res.signature {
+            Some(v) => Ok(hex::decode(v)?),
+            None => {
+                Err(TwetchError::Signing("remote signer returned no signature".to_string()).into())
+            }
+        }
+    }
+
+    async fn sign_typed(&self, typed_signing: &TypedSigning) -> Result<TypedSigning> {
+        let res = self
+            .request(&SignerRequest::SignTyped {
+                typed_signing: typed_signing.clone(),
+            })
+            .await?;
+
+        match res.typed_signing {
+            Some(v) => Ok(v),
+            None => Err(TwetchError::Signing(
+                "remote signer returned no typed signing".to_string(),
+            )
+            .into()),
+        }
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut Transaction,
+        utxos: &Vec<Option<UTXO>>,
+    ) -> Result<()> {
+        let res = self
+            .request(&SignerRequest::SignTransaction {
+                tx: tx.to_compact_hex()?,
+                utxos: utxos.clone(),
+            })
+            .await?;
+
+        match res.tx {
+            Some(v) => *tx = Transaction::from_compact_hex(&v)?,
+            None => {
+                return Err(TwetchError::Signing(
+                    "remote signer returned no signed transaction".to_string(),
+                )
+                .into())
+            }
+        };
+
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{constants, Networks, PolynymApi, TxlogApi, Wallet, UTXO};
+use crate::{
+    constants, CoinSelection, CoinSelector, FeePolicy, FeeRates, Networks, Outpoint, PolynymApi,
+    Recipient, RecipientError, ReservationGuard, SdkConfig, TwetchError, TxlogApi, Wallet, UTXO,
+};
 use anyhow::Result;
 use bsv::{P2PKHAddress, Script, Transaction, TxIn, TxOut, VarInt};
 use serde::{Deserialize, Serialize};
@@ -33,7 +36,7 @@ pub struct PaymentDestination {
     pub reference: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct TxBuilder {
     pub network: Networks,
     pub contract: Option<String>,
@@ -42,6 +45,86 @@ pub struct TxBuilder {
     pub outputs: Vec<TxBuilderOutput>,
     pub change_address: Option<P2PKHAddress>,
     pub auto_fund: bool,
+    #[serde(default)]
+    pub coin_selection: CoinSelection,
+    #[serde(default)]
+    pub fee_policy: FeePolicy,
+    /// Funds `auto_fund` from these instead of fetching the wallet utxos.
+    #[serde(default)]
+    pub utxos: Option<Vec<UTXO>>,
+    /// Values of the `extended_tx` inputs, instead of looking them up on txlog.
+    #[serde(default)]
+    pub prevouts: Vec<Prevout>,
+    /// Never touch the network. Builds fail when something is missing:
+    /// `utxos` to fund, a prevout, or a `to` recipient that isn't an address.
+    #[serde(default)]
+    pub offline: bool,
+    /// Leave out outputs whose recipient can't be resolved instead of failing,
+    /// they are listed in `BuiltTx.unresolved_recipients`.
+    #[serde(default)]
+    pub allow_partial_resolution: bool,
+    /// Sweep mode, spends every utxo (all of `utxos` when given) but frozen
+    /// ones and token outputs, and sends what is left after `outputs` and the
+    /// fee to this recipient.
+    #[serde(default)]
+    pub sweep_to: Option<String>,
+    /// Spends exactly these utxos, frozen or not, instead of running coin selection.
+    #[serde(default)]
+    pub coin_control: Vec<Outpoint>,
+}
+
+/// Keys `Wallet::sweep` can move funds from.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepSource {
+    Wif(String),
+    /// Derivation path from the root of the wallet's seed, the `xpriv`, e.g.
+    /// `m/44'/0'/0'/0/3` of an older policy.
+    Path(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Prevout {
+    pub txid: String,
+    pub vout: u32,
+    pub satoshis: u64,
+}
+
+/// Output paying one of the wallet's own keys, spent with `UTXO.path` = `path`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WalletOutput {
+    pub vout: u32,
+    pub path: i32,
+}
+
+/// Kind of unlocking script an input is signed with, sizing it for fees.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputScriptType {
+    P2PKH,
+    Sigil,
+    Custom { unlocking_script_size: usize },
+}
+
+impl InputScriptType {
+    pub fn from_locking_script(script: &Script) -> InputScriptType {
+        let bytes = script.to_bytes();
+
+        match bytes.as_slice() {
+            [0x76, 0xa9, 0x14, .., 0x88, 0xac] if bytes.len() == 25 => InputScriptType::P2PKH,
+            _ => InputScriptType::Sigil,
+        }
+    }
+
+    pub fn unlocking_script_size(&self) -> usize {
+        match self {
+            InputScriptType::P2PKH => constants::P2PKH_UNLOCKING_SCRIPT_SIZE,
+            InputScriptType::Sigil => constants::SIGIL_V2_UNLOCKING_SCRIPT_SIZE,
+            InputScriptType::Custom {
+                unlocking_script_size,
+            } => *unlocking_script_size,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -50,22 +133,56 @@ pub struct BuiltTx {
     pub txid: String,
     pub total_cost_sats: u64,
     pub fee_sats: u64,
+    /// Sats per byte, estimated until the transaction is signed.
+    #[serde(default)]
+    pub fee_rate: f64,
+    /// Lowest rate the signed transaction may pay, from the build's `FeePolicy`.
+    #[serde(default = "default_min_fee_rate")]
+    pub min_fee_rate: f64,
     pub payment_destinations: Vec<PaymentDestination>,
     pub encrypted_hash: Option<String>,
     pub nfts: Vec<String>,
     pub typed_signing: Option<TypedSigning>,
+    /// Funding utxos by input index, kept so an unsigned transaction can be signed later.
+    #[serde(default)]
+    pub utxos: Vec<Option<UTXO>>,
+    #[serde(default)]
+    pub contract: Option<String>,
+    /// Utxo path of the change output, `None` when it went to `TxBuilder.change_address`.
+    #[serde(default)]
+    pub change_path: Option<i32>,
+    #[serde(default)]
+    pub unresolved_recipients: Vec<String>,
+    /// Outputs other than the change that pay the wallet's own keys.
+    #[serde(default)]
+    pub wallet_outputs: Vec<WalletOutput>,
+    /// Locks the funding utxos, commit or release it through `Wallet::reservations`
+    /// once the transaction is broadcast or abandoned.
+    #[serde(default)]
+    pub reservation: Option<String>,
+    #[serde(default = "default_signed")]
+    pub signed: bool,
+}
+
+fn default_signed() -> bool {
+    true
+}
+
+fn default_min_fee_rate() -> f64 {
+    constants::MIN_TX_FEE_RATE
 }
 
 impl TxBuilder {
     pub fn estimate_cost(tx: &Transaction, wallet: &Wallet) -> Result<u64> {
         let mut cost = 0;
+        let account_script_hex = wallet.account_locking_script()?.to_hex();
 
         for i in 0..tx.get_noutputs() {
             let tx_out = tx.get_output(i).unwrap();
 
             if !tx_out
                 .get_script_pub_key_hex()
-                .contains(&wallet.account_locking_script()?.to_hex())
+                .contains(&account_script_hex)
             {
                 cost = cost + tx_out.get_satoshis();
             }
@@ -74,42 +191,122 @@ impl TxBuilder {
         Ok(cost)
     }
 
-    pub fn estimate_size(tx: &Transaction, utxos: &Vec<Option<UTXO>>) -> Result<usize> {
+    /// Unlocking script the input will carry once signed. Inputs that are
+    /// already signed, or whose utxo declares a type, are taken as they are.
+    /// Otherwise wallet utxos are P2PKH and inputs brought in by an extended or
+    /// typed signing transaction are sigil contracts, unless their locking
+    /// script says otherwise.
+    pub fn input_script_type(tx_in: Option<&TxIn>, utxo: Option<&UTXO>) -> InputScriptType {
+        if let Some(v) = utxo.and_then(|e| e.script_type.clone()) {
+            return v;
+        }
+
+        if let Some(tx_in) = tx_in {
+            let unlocking_script = tx_in.get_unlocking_script().to_bytes();
+            if !unlocking_script.is_empty() {
+                return InputScriptType::Custom {
+                    unlocking_script_size: unlocking_script.len(),
+                };
+            }
+        }
+
+        let locking_script = match utxo.and_then(|e| e.script.clone()) {
+            Some(v) => Some(v),
+            None => tx_in.and_then(|e| e.get_locking_script()),
+        };
+
+        match (locking_script, utxo) {
+            (Some(v), _) => InputScriptType::from_locking_script(&v),
+            (None, Some(_)) => InputScriptType::P2PKH,
+            (None, None) => InputScriptType::Sigil,
+        }
+    }
+
+    /// Whether `build` adds inputs from the wallet, and change with them.
+    pub fn is_funded(&self) -> bool {
+        self.auto_fund || self.sweep_to.is_some() || !self.coin_control.is_empty()
+    }
+
+    /// Size of the change output `build` adds when funding the transaction.
+    pub fn change_output_size(builder: &TxBuilder) -> Result<usize> {
+        if !builder.is_funded() {
+            return Ok(0);
+        }
+
+        match &builder.change_address {
+            Some(v) => Ok(TxOut::new(0, &v.get_locking_script()?).to_bytes()?.len()),
+            None => Ok(constants::P2PKH_OUTPUT_SIZE),
+        }
+    }
+
+    /// `utxos` lines up with the inputs of `tx` and may run past them, for
+    /// inputs that are yet to be added. `change_size` is 0 without change.
+    pub fn estimate_size(
+        tx: &Transaction,
+        utxos: &Vec<Option<UTXO>>,
+        change_size: usize,
+    ) -> Result<usize> {
         let mut size = constants::TX_VERSION_SIZE;
-        size = size + VarInt::get_varint_size((tx.get_ninputs()) as u64);
+        size = size + VarInt::get_varint_size(utxos.len() as u64);
+
+        for (i, utxo) in utxos.iter().enumerate() {
+            let tx_in = tx.get_input(i);
+            let script_size =
+                TxBuilder::input_script_type(tx_in.as_ref(), utxo.as_ref()).unlocking_script_size();
+
+            size = size
+                + constants::TXIN_TXID_SIZE
+                + constants::TXIN_VOUT_SIZE
+                + VarInt::get_varint_size(script_size as u64)
+                + script_size
+                + constants::TXIN_SEQUENCE_SIZE;
+        }
 
-        size = size
-            + utxos
-                .iter()
-                .map(|e| {
-                    let script_size = match e {
-                        Some(_) => constants::SIGIL_V2_UNLOCKING_SCRIPT_SIZE,
-                        None => constants::P2PKH_UNLOCKING_SCRIPT_SIZE,
-                    };
-
-                    constants::TXIN_TXID_SIZE
-                        + constants::TXIN_VOUT_SIZE
-                        + VarInt::get_varint_size(script_size as u64)
-                        + script_size
-                        + constants::TXIN_SEQUENCE_SIZE
-                })
-                .sum::<usize>();
-
-        size = size + VarInt::get_varint_size((tx.get_noutputs() + 1) as u64); // +1 for change address
+        let noutputs = match change_size {
+            0 => tx.get_noutputs(),
+            _ => tx.get_noutputs() + 1,
+        };
+        size = size + VarInt::get_varint_size(noutputs as u64);
 
         for i in 0..tx.get_noutputs() {
             let tx_out = tx.get_output(i).unwrap();
             size = size + tx_out.to_bytes()?.len();
         }
 
-        // change addres
-        size = size + constants::P2PKH_OUTPUT_SIZE;
+        size = size + change_size;
 
         size = size + constants::TX_LOCKTIME_SIZE;
 
         Ok(size)
     }
 
+    /// Bytes of the OP_RETURN outputs, priced at the data fee rate.
+    pub fn estimate_data_size(tx: &Transaction) -> Result<usize> {
+        let mut size = 0;
+
+        for i in 0..tx.get_noutputs() {
+            let tx_out = tx.get_output(i).unwrap();
+            let script = tx_out.get_script_pub_key().to_bytes();
+
+            if script.starts_with(&[0x6a]) || script.starts_with(&[0x00, 0x6a]) {
+                size = size + tx_out.to_bytes()?.len();
+            }
+        }
+
+        Ok(size)
+    }
+
+    pub fn estimate_fee(
+        tx: &Transaction,
+        utxos: &Vec<Option<UTXO>>,
+        change_size: usize,
+        rates: &FeeRates,
+    ) -> Result<u64> {
+        let size = TxBuilder::estimate_size(tx, utxos, change_size)?;
+        let data_size = TxBuilder::estimate_data_size(tx)?;
+        Ok(rates.fee(size - data_size, data_size))
+    }
+
     pub async fn resolve_output(
         output: &TxBuilderOutput,
         wallet: &Wallet,
@@ -124,44 +321,56 @@ impl TxBuilder {
                 &P2PKHAddress::from_string(address)?.get_locking_script()?,
             ));
         } else if let Some(to) = &output.to {
-            let polynym = PolynymApi::new(constants::POLYNYM_URL.to_string());
-
-            // Addresses
-            if let Ok(address) = P2PKHAddress::from_string(to) {
-                tx_outs.push(TxOut::new(output.sats, &address.get_locking_script()?));
-            } else if to.contains("@") && !to.starts_with("@") {
-                // User Paymails
-                if let Ok(p2p_payment_destination) =
-                    polynym.p2p_payment_destination(to, output.sats).await
-                {
-                    payment_destination = Some(PaymentDestination {
-                        paymail: to.clone(),
-                        reference: p2p_payment_destination.reference,
-                    });
-
-                    for o in p2p_payment_destination.outputs.iter() {
-                        tx_outs.push(TxOut::new(o.satoshis, &Script::from_hex(&o.script)?));
-                    }
-                } else {
-                    if let Ok(address) = polynym.get_address(to).await {
-                        tx_outs.push(TxOut::new(output.sats, &address.get_locking_script()?));
+            let polynym = PolynymApi::new(wallet.config().polynym_url.clone());
+
+            match Recipient::parse(to)? {
+                Recipient::Address(v) => {
+                    tx_outs.push(TxOut::new(
+                        output.sats,
+                        &P2PKHAddress::from_string(&v)?.get_locking_script()?,
+                    ));
+                }
+                Recipient::Paymail(v) => {
+                    if let Ok(p2p_payment_destination) =
+                        polynym.p2p_payment_destination(&v, output.sats).await
+                    {
+                        payment_destination = Some(PaymentDestination {
+                            paymail: v.clone(),
+                            reference: p2p_payment_destination.reference,
+                        });
+
+                        for o in p2p_payment_destination.outputs.iter() {
+                            tx_outs.push(TxOut::new(o.satoshis, &Script::from_hex(&o.script)?));
+                        }
+                    } else {
+                        match polynym.get_address(&v).await {
+                            Ok(address) => tx_outs
+                                .push(TxOut::new(output.sats, &address.get_locking_script()?)),
+                            Err(e) => return Err(RecipientError::new(to, &format!("{}", e)).into()),
+                        }
                     }
                 }
-            } else {
-                // User Number
-                let search = match to.parse::<u64>() {
-                    Ok(v) => format!("@{}", v),
-                    Err(_) => to.clone(),
-                };
+                recipient => match polynym.get_address(&recipient.polynym_query()).await {
+                    Ok(address) => {
+                        tx_outs.push(TxOut::new(output.sats, &address.get_locking_script()?))
+                    }
+                    Err(e) => return Err(RecipientError::new(to, &format!("{}", e)).into()),
+                },
+            }
 
-                if let Ok(address) = polynym.get_address(&search).await {
-                    tx_outs.push(TxOut::new(output.sats, &address.get_locking_script()?));
-                }
+            if tx_outs.is_empty() {
+                return Err(RecipientError::new(to, "no payment outputs").into());
             }
         } else if let Some(script_string) = &output.script {
             let script = match Script::from_asm_string(&script_string) {
                 Ok(v) => v,
-                Err(_) => anyhow::bail!("failed to resolve 'script'"),
+                Err(e) => {
+                    return Err(TwetchError::Resolution(format!(
+                        "failed to resolve script '{}', {}",
+                        script_string, e
+                    ))
+                    .into())
+                }
             };
             tx_outs.push(TxOut::new(output.sats, &script));
         } else if let Some(args) = &output.args {
@@ -204,6 +413,47 @@ impl TxBuilder {
         })
     }
 
+    pub fn coin_control_utxos(outpoints: &Vec<Outpoint>, available: &[UTXO]) -> Result<Vec<UTXO>> {
+        let mut selected = Vec::new();
+
+        for outpoint in outpoints {
+            match available.iter().find(|e| e.outpoint() == *outpoint) {
+                Some(v) => selected.push(v.clone()),
+                None => {
+                    return Err(TwetchError::Resolution(format!(
+                        "coin control utxo {} is not spendable",
+                        outpoint
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        Ok(selected)
+    }
+
+    /// Locking script paying a recipient, for outputs whose amount isn't known
+    /// upfront so paymail p2p destinations can't be requested.
+    pub async fn resolve_recipient_script(
+        to: &str,
+        offline: bool,
+        config: &SdkConfig,
+    ) -> Result<Script> {
+        match Recipient::parse(to)? {
+            Recipient::Address(v) => Ok(P2PKHAddress::from_string(&v)?.get_locking_script()?),
+            _ if offline => {
+                Err(RecipientError::new(to, "offline builds only pay addresses").into())
+            }
+            recipient => {
+                let polynym = PolynymApi::new(config.polynym_url.clone());
+                match polynym.get_address(&recipient.polynym_query()).await {
+                    Ok(address) => Ok(address.get_locking_script()?),
+                    Err(e) => Err(RecipientError::new(to, &format!("{}", e)).into()),
+                }
+            }
+        }
+    }
+
     pub fn find_nft(tx_out: TxOut) -> Option<NFT> {
         let data = match tx_out
             .get_script_pub_key()
@@ -233,16 +483,64 @@ impl TxBuilder {
         Some(nft)
     }
 
+    /// Value of an input from the extended transaction, the builder prevouts or txlog.
+    pub async fn prevout_satoshis(
+        builder: &TxBuilder,
+        tx_in: &TxIn,
+        config: &SdkConfig,
+    ) -> Result<u64> {
+        if let Some(v) = tx_in.get_satoshis() {
+            return Ok(v);
+        }
+
+        let txid = tx_in.get_prev_tx_id_hex(None);
+        let vout = tx_in.get_vout();
+
+        if let Some(v) = builder
+            .prevouts
+            .iter()
+            .find(|e| e.txid == txid && e.vout == vout)
+        {
+            return Ok(v.satoshis);
+        }
+
+        if builder.offline {
+            return Err(TwetchError::Resolution(format!(
+                "offline build is missing prevout {}:{}",
+                txid, vout
+            ))
+            .into());
+        }
+
+        let txlog_api = TxlogApi::new(config.txlog_url.clone());
+        match txlog_api.satoshis(&txid, vout).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.context(TwetchError::Resolution(format!(
+                "failed to fetch prev tx {}:{}",
+                txid, vout
+            )))),
+        }
+    }
+
     pub async fn build(builder: &TxBuilder, wallet: &Wallet) -> Result<BuiltTx> {
+        TxBuilder::build_with_selector(builder, wallet, &builder.coin_selection).await
+    }
+
+    /// Same as `build`, funding the transaction with a custom `CoinSelector`.
+    pub async fn build_with_selector(
+        builder: &TxBuilder,
+        wallet: &Wallet,
+        selector: &dyn CoinSelector,
+    ) -> Result<BuiltTx> {
         let mut output_sats = 0_u64;
         let mut input_sats = 0_u64;
         let mut utxos: Vec<Option<UTXO>> = Vec::new();
         let mut payment_destinations: Vec<PaymentDestination> = Vec::new();
         let mut encrypted_hash = None;
         let mut nfts: Vec<String> = Vec::new();
-        let mut typed_signing: Option<TypedSigning> = None;
-
-        let change_script = wallet.account_locking_script()?;
+        let mut change_path: Option<i32> = None;
+        let mut unresolved_recipients: Vec<String> = Vec::new();
+        let mut reservation: Option<ReservationGuard> = None;
 
         let mut tx = match &builder.extended_tx {
             Some(v) => Transaction::from_compact_hex(v)?,
@@ -270,26 +568,34 @@ impl TxBuilder {
 
         // TODO : make this work for many privs
         for i in 0..tx.get_ninputs() {
-            let tx_in = tx.get_input(i).unwrap();
+            let mut tx_in = tx.get_input(i).unwrap();
 
-            let txlog_api = TxlogApi::new(constants::urls::TXLOG_URL.to_string());
-            let satoshis_in = match txlog_api
-                .satoshis(&tx_in.get_prev_tx_id_hex(None), tx_in.get_vout())
-                .await
-            {
-                Ok(v) => v,
-                Err(_) => anyhow::bail!("failed to fetch prev tx"),
-            };
+            let satoshis = TxBuilder::prevout_satoshis(builder, &tx_in, wallet.config()).await?;
+            input_sats += satoshis;
 
-            input_sats += satoshis_in;
+            tx_in.set_satoshis(satoshis);
+            tx.set_input(i, &tx_in);
 
             utxos.push(None);
         }
 
         for output in &builder.outputs {
-            let res = match TxBuilder::resolve_output(output, wallet).await {
+            let resolved = match (builder.offline, &output.to) {
+                (true, Some(to)) if !matches!(Recipient::parse(to), Ok(Recipient::Address(_))) => {
+                    Err(RecipientError::new(to, "offline builds only pay addresses").into())
+                }
+                _ => TxBuilder::resolve_output(output, wallet).await,
+            };
+
+            let res = match resolved {
                 Ok(v) => v,
-                Err(_) => anyhow::bail!("failed to resolve output"),
+                Err(e) => match (builder.allow_partial_resolution, &output.to) {
+                    (true, Some(to)) if e.is::<RecipientError>() => {
+                        unresolved_recipients.push(to.clone());
+                        continue;
+                    }
+                    _ => return Err(e),
+                },
             };
 
             if let Some(p) = res.payment_destination {
@@ -306,68 +612,237 @@ impl TxBuilder {
             }
         }
 
-        if builder.auto_fund == true {
-            let wallet_utxos = match wallet.utxos(&builder.network, output_sats + 100000).await {
-                Ok(v) => v,
-                Err(_) => anyhow::bail!("failed to fetch utxos"),
+        let change_size = TxBuilder::change_output_size(builder)?;
+        if let (true, FeePolicy::Mapi { .. }) = (builder.offline, &builder.fee_policy) {
+            return Err(TwetchError::Resolution(
+                "offline build can't fetch a mAPI fee quote".to_string(),
+            )
+            .into());
+        }
+
+        let rates = match builder.fee_policy.resolve().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.context("failed to resolve fee policy")),
+        };
+
+        let sweep_script = match &builder.sweep_to {
+            Some(to) => Some(
+                TxBuilder::resolve_recipient_script(to, builder.offline, wallet.config()).await?,
+            ),
+            None => None,
+        };
+
+        // a sweep sends everything left over to its destination instead of change
+        let change_size = match &sweep_script {
+            Some(v) => TxOut::new(0, v).to_bytes()?.len(),
+            None => change_size,
+        };
+        let coin_control = !builder.coin_control.is_empty();
+        let funded = builder.is_funded();
+
+        if funded {
+            let amount = match (&sweep_script, coin_control) {
+                (None, false) => output_sats + 100000,
+                _ => u64::MAX,
             };
 
-            let balance: u64 = wallet_utxos.iter().map(|e| e.satoshis).sum();
-            anyhow::ensure!(
-                balance >= output_sats,
-                format!(
-                    "Insufficient wallet balance : {:.8} BSV - {:.8} BSV",
-                    balance as f64 / 1e8,
-                    output_sats as f64 / 1e8
+            let wallet_utxos = match &builder.utxos {
+                Some(v) => v.clone(),
+                None if builder.offline => {
+                    return Err(
+                        TwetchError::Resolution("offline build requires utxos".to_string()).into(),
+                    )
+                }
+                None => match wallet.all_utxos(&builder.network, amount).await {
+                    Ok(v) => v,
+                    Err(e) => return Err(e.context("failed to fetch utxos")),
+                },
+            };
+
+            // change already paid to us tells where fresh change keys start
+            wallet.observe_change_paths(&wallet_utxos);
+
+            // frozen utxos and token outputs are only spent when picked through coin control
+            let unfrozen: Vec<UTXO> = wallet_utxos
+                .iter()
+                .filter(|e| !wallet.is_frozen(&e.outpoint()) && !e.is_token_output())
+                .cloned()
+                .collect();
+
+            // inputs already on the transaction count towards the fee
+            let surplus = input_sats.saturating_sub(output_sats);
+            let fee = |selected: &[UTXO]| -> Result<u64> {
+                let mut candidate = utxos.clone();
+                candidate.extend(selected.iter().map(|e| Some(e.clone())));
+                Ok(
+                    TxBuilder::estimate_fee(&tx, &candidate, change_size, &rates)?
+                        .saturating_sub(surplus),
                 )
-            );
+            };
 
-            let (paymail_utxos, account_utxos): (Vec<UTXO>, Vec<UTXO>) =
-                wallet_utxos.into_iter().partition(|v| v.path != -1);
+            // utxos reserved by a concurrent build are skipped, retrying when
+            // another build reserves ours between selecting and reserving
+            let mut attempts = 0;
+            let selected = loop {
+                let available: Vec<UTXO> = unfrozen
+                    .iter()
+                    .filter(|e| !wallet.reservations().is_reserved(&e.outpoint()))
+                    .cloned()
+                    .collect();
+
+                let selected = if coin_control {
+                    TxBuilder::coin_control_utxos(&builder.coin_control, &wallet_utxos)?
+                } else if sweep_script.is_some() {
+                    available
+                } else {
+                    selector.select(&available, output_sats.saturating_sub(input_sats), &fee)?
+                };
+
+                let outpoints: Vec<Outpoint> = selected.iter().map(|e| e.outpoint()).collect();
+                match ReservationGuard::new(wallet.reservations(), &outpoints) {
+                    Ok(v) => {
+                        reservation = Some(v);
+                        break selected;
+                    }
+                    Err(_) if attempts < 3 && !coin_control => attempts += 1,
+                    Err(e) => return Err(e),
+                }
+            };
+
+            for utxo in &selected {
+                let txid = match hex::decode(&utxo.txid) {
+                    Ok(v) if v.len() == 32 => v,
+                    _ => {
+                        return Err(TwetchError::Resolution(format!(
+                            "utxo {} has an invalid txid",
+                            utxo.outpoint()
+                        ))
+                        .into())
+                    }
+                };
 
-            for utxo in &paymail_utxos {
                 input_sats += utxo.satoshis;
                 utxos.push(Some(utxo.clone()));
-                tx.add_input(&TxIn::new(
-                    &hex::decode(utxo.txid.clone()).unwrap(),
-                    utxo.vout,
-                    &Script::default(),
-                    None,
-                ))
+                tx.add_input(&TxIn::new(&txid, utxo.vout, &Script::default(), None))
             }
+        }
 
-            for utxo in &account_utxos {
-                if input_sats < output_sats + 100000 {
-                    input_sats += utxo.satoshis;
-                    utxos.push(Some(utxo.clone()));
-                    tx.add_input(&TxIn::new(
-                        &hex::decode(utxo.txid.clone()).unwrap(),
-                        utxo.vout,
-                        &Script::default(),
-                        None,
-                    ))
+        let mut fee_sats = TxBuilder::estimate_fee(&tx, &utxos, change_size, &rates)?;
+        let change_sats: i64 = input_sats as i64 - output_sats as i64 - fee_sats as i64;
+
+        if funded {
+            if change_sats < 0 {
+                return Err(TwetchError::InsufficientFunds {
+                    required: output_sats + fee_sats,
+                    available: input_sats,
                 }
+                .into());
+            }
+
+            if (change_sats as u64) < constants::MIN_CHANGE_SATS {
+                if sweep_script.is_some() {
+                    return Err(TwetchError::InsufficientFunds {
+                        required: output_sats + fee_sats + constants::MIN_CHANGE_SATS,
+                        available: input_sats,
+                    }
+                    .into());
+                }
+                fee_sats += change_sats as u64;
             }
         }
 
-        let size = TxBuilder::estimate_size(&tx, &utxos)?;
-        let fee_sats: u64 = ((size as f64) * constants::TX_FEE_RATE).ceil() as u64;
-        let change_sats: i64 = input_sats as i64 - output_sats as i64 - fee_sats as i64;
+        let has_change = funded && change_sats as u64 >= constants::MIN_CHANGE_SATS;
+        let mut total_cost_sats = fee_sats + TxBuilder::estimate_cost(&tx, &wallet)?;
+        if sweep_script.is_some() {
+            total_cost_sats += change_sats as u64;
+        }
+        let fee_rate = fee_sats as f64
+            / TxBuilder::estimate_size(&tx, &utxos, if has_change { change_size } else { 0 })?
+                as f64;
+
+        if has_change {
+            let change_script = match (&sweep_script, &builder.change_address) {
+                (Some(v), _) => v.clone(),
+                (None, Some(v)) => v.get_locking_script()?,
+                (None, None) => {
+                    let (script, path) = wallet.next_change_script()?;
+                    change_path = Some(path);
+                    script
+                }
+            };
 
-        let total_cost_sats = fee_sats + TxBuilder::estimate_cost(&tx, &wallet)?;
+            tx.add_output(&TxOut::new(change_sats as u64, &change_script));
+        }
 
-        if builder.auto_fund == true {
-            if change_sats > 0 {
-                tx.add_output(&TxOut::new(change_sats as u64, &change_script));
-            } else {
-                anyhow::bail!("Insufficient transaction fees");
+        // a failed build hands its change key back, leaving no gap in the change paths
+        let release_change = || {
+            if let Some(path) = change_path {
+                wallet.release_change_path(path);
             }
+        };
+
+        let txid = match tx.get_id_hex() {
+            Ok(v) => v,
+            Err(e) => {
+                release_change();
+                return Err(e.into());
+            }
+        };
+        let mut built = BuiltTx {
+            tx,
+            txid,
+            encrypted_hash,
+            total_cost_sats,
+            fee_sats,
+            fee_rate,
+            min_fee_rate: rates.min_rate(),
+            payment_destinations,
+            nfts,
+            typed_signing: builder.typed_signing.clone(),
+            utxos,
+            contract: builder.contract.clone(),
+            change_path,
+            unresolved_recipients,
+            wallet_outputs: Vec::new(),
+            reservation: None,
+            signed: false,
+        };
+
+        // watch-only wallets hand back the unsigned transaction
+        if !wallet.is_watch_only() {
+            built = match TxBuilder::sign(&built, wallet).await {
+                Ok(v) => v,
+                Err(e) => {
+                    release_change();
+                    return Err(e);
+                }
+            };
         }
 
-        wallet.sign_transaction(&mut tx, &utxos)?;
+        built.reservation = reservation.and_then(|e| e.keep());
+
+        Ok(built)
+    }
+
+    pub async fn sign(built: &BuiltTx, wallet: &Wallet) -> Result<BuiltTx> {
+        anyhow::ensure!(!built.signed, "transaction is already signed");
+
+        let mut tx = built.tx.clone();
+        let mut typed_signing: Option<TypedSigning> = None;
+
+        wallet.sign_transaction(&mut tx, &built.utxos).await?;
 
-        if let Some(ts) = &builder.typed_signing {
-            let ts = wallet.sign_typed(&mut ts.clone())?;
+        if let Some(ts) = &built.typed_signing {
+            let ts = wallet.sign_typed(ts).await?;
+            let contract = match &built.contract {
+                Some(v) => v,
+                None => {
+                    return Err(TwetchError::Signing(
+                        "typed signing requires a contract".to_string(),
+                    )
+                    .into())
+                }
+            };
 
             for signature in &ts.signatures {
                 if let Some(sig) = &signature.signature {
@@ -375,11 +850,20 @@ impl TxBuilder {
                         "{} {} {}",
                         hex::encode(sig.clone()),
                         wallet.account_public_key()?.to_hex()?,
-                        builder.contract.clone().unwrap()
+                        contract
                     );
 
-                    let vin = signature.vin.unwrap();
-                    let mut input = tx.get_input(vin).unwrap();
+                    let (vin, mut input) =
+                        match signature.vin.and_then(|e| Some((e, tx.get_input(e)?))) {
+                            Some(v) => v,
+                            None => {
+                                return Err(TwetchError::Signing(format!(
+                                    "typed signature for a missing input {:?}",
+                                    signature.vin
+                                ))
+                                .into())
+                            }
+                        };
                     input.set_unlocking_script(&Script::from_asm_string(&asm)?);
                     tx.set_input(vin, &input);
                 }
@@ -391,20 +875,20 @@ impl TxBuilder {
             });
         }
 
-        let fee_rate = fee_sats as f64 / tx.get_size().unwrap() as f64;
-        if fee_rate < constants::MIN_TX_FEE_RATE {
-            anyhow::bail!("Fee rate too low ({:.3} sats/byte)", fee_rate);
+        let fee_rate = built.fee_sats as f64 / tx.get_size()? as f64;
+        if fee_rate < built.min_fee_rate {
+            return Err(
+                TwetchError::Fee(format!("fee rate too low ({:.3} sats/byte)", fee_rate)).into(),
+            );
         }
 
         Ok(BuiltTx {
             tx: tx.clone(),
-            txid: tx.get_id_hex()?.clone(),
-            encrypted_hash,
-            total_cost_sats,
-            fee_sats,
-            payment_destinations,
-            nfts,
+            txid: tx.get_id_hex()?,
+            fee_rate,
             typed_signing,
+            signed: true,
+            ..built.clone()
         })
     }
 }
@@ -1,4 +1,7 @@
-use crate::{constants, MetasyncApi, Networks, UtxoDetectiveApi, WhatsOnChainApi};
+use crate::{
+    InputScriptType, MetasyncProvider, Networks, Outpoint, UtxoDetectiveProvider, UtxoProvider,
+    UtxoQuery, WhatsOnChainProvider,
+};
 use anyhow::Result;
 use bsv::{P2PKHAddress, PublicKey, Script};
 use serde::{Deserialize, Serialize};
@@ -10,62 +13,47 @@ pub struct UTXO {
     pub satoshis: u64,
     pub path: i32,
     pub script: Option<Script>,
+    /// Block height the utxo was mined at, `None` while unconfirmed or unknown.
+    #[serde(default)]
+    pub height: Option<i64>,
+    /// Declares how the utxo is unlocked, for scripts `TxBuilder` can't recognise.
+    #[serde(default)]
+    pub script_type: Option<InputScriptType>,
 }
 
 impl UTXO {
-    pub async fn from_woc(address: &P2PKHAddress, network: &Networks) -> Result<Vec<UTXO>> {
-        let whatsonchain = WhatsOnChainApi::new(constants::WHATSONCHAIN_URL.to_string());
-        let utxos = whatsonchain
-            .utxos(address, network)
-            .await?
-            .iter()
-            .map(|e| UTXO {
-                txid: e.tx_hash.clone(),
-                vout: e.tx_pos,
-                satoshis: e.value,
-                path: -1,
-                script: None,
-            })
-            .collect();
+    pub fn outpoint(&self) -> Outpoint {
+        Outpoint::new(&self.txid, self.vout)
+    }
 
-        Ok(utxos)
+    /// 1 sat outputs most likely carry a token, spending them burns it.
+    pub fn is_token_output(&self) -> bool {
+        self.satoshis == 1
     }
 
-    pub async fn from_metasync(public_key: &PublicKey, network: &Networks) -> Result<Vec<UTXO>> {
-        let metasync = MetasyncApi::new(constants::METASYNC_URL.to_string());
+    pub async fn from_woc(address: &P2PKHAddress, network: &Networks) -> Result<Vec<UTXO>> {
+        WhatsOnChainProvider::default()
+            .address_utxos(address, network)
+            .await
+    }
 
-        let utxos = metasync
-            .utxos(public_key, network)
-            .await?
-            .iter()
-            .map(|e| UTXO {
-                txid: e.txid.clone(),
-                vout: e.vout,
-                satoshis: e.satoshis.parse::<u64>().unwrap(),
-                path: e.path.parse::<i32>().unwrap(),
-                script: None,
+    pub async fn from_metasync(public_key: &PublicKey, network: &Networks) -> Result<Vec<UTXO>> {
+        MetasyncProvider::default()
+            .utxos(&UtxoQuery {
+                account_public_key: public_key.clone(),
+                network: network.clone(),
+                amount: 0,
             })
-            .collect();
-
-        Ok(utxos)
+            .await
     }
 
     pub async fn from_utxo_detective(public_key: &PublicKey, amount: u64) -> Result<Vec<UTXO>> {
-        let utxo_detective = UtxoDetectiveApi::new(constants::UTXO_DETECTIVE_URL.to_string());
-
-        let utxos = utxo_detective
-            .utxos(public_key, amount)
-            .await?
-            .iter()
-            .map(|e| UTXO {
-                txid: e.txid.clone(),
-                vout: e.vout,
-                satoshis: e.satoshis.parse::<u64>().unwrap(),
-                path: e.path.parse::<i32>().unwrap(),
-                script: None,
+        UtxoDetectiveProvider::default()
+            .utxos(&UtxoQuery {
+                account_public_key: public_key.clone(),
+                network: Networks::BSV,
+                amount,
             })
-            .collect();
-
-        Ok(utxos)
+            .await
     }
 }
@@ -0,0 +1,370 @@
+use crate::{
+    MetasyncApi, Networks, Outpoint, SdkConfig, TwetchError, UtxoDetectiveApi, WhatsOnChainApi,
+    UTXO,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use bsv::{ExtendedPublicKey, P2PKHAddress, PublicKey};
+use futures_util::future::join_all;
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+    sync::Arc,
+};
+
+pub struct UtxoQuery {
+    pub account_public_key: PublicKey,
+    pub network: Networks,
+    /// Satoshis the caller is after, providers may return more or less.
+    pub amount: u64,
+}
+
+/// Where a wallet looks up its utxos, see `Wallet::with_utxo_provider`.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait UtxoProvider: Send + Sync {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>>;
+}
+
+/// Paymail path utxos of the account public key, enough to cover `amount`.
+pub struct UtxoDetectiveProvider {
+    api: UtxoDetectiveApi,
+}
+
+impl UtxoDetectiveProvider {
+    pub fn new(url: String) -> UtxoDetectiveProvider {
+        UtxoDetectiveProvider {
+            api: UtxoDetectiveApi::new(url),
+        }
+    }
+}
+
+impl Default for UtxoDetectiveProvider {
+    fn default() -> UtxoDetectiveProvider {
+        UtxoDetectiveProvider::new(SdkConfig::current().utxo_detective_url)
+    }
+}
+
+impl UtxoDetectiveProvider {
+    /// The paymail utxo index doesn't report block heights, this looks up the
+    /// address of every path among `utxos` that lacks them and fills them in.
+    pub async fn fill_heights(
+        &self,
+        utxos: &mut [UTXO],
+        wallet_xpub: &ExtendedPublicKey,
+    ) -> Result<()> {
+        let paths: BTreeSet<i32> = utxos
+            .iter()
+            .filter(|e| e.path >= 0 && e.height.is_none())
+            .map(|e| e.path)
+            .collect();
+
+        let mut addresses = Vec::new();
+        for path in paths {
+            addresses.push(
+                wallet_xpub
+                    .derive(path as u32)?
+                    .get_public_key()
+                    .to_p2pkh_address()?
+                    .to_string()?,
+            );
+        }
+
+        let mut heights: HashMap<Outpoint, i64> = HashMap::new();
+        for res in join_all(addresses.iter().map(|e| self.api.utxos_by_address(e))).await {
+            for utxo in res? {
+                if let Some(height) = utxo.block_height.filter(|v| *v > 0) {
+                    heights.insert(Outpoint::new(&utxo.txid, utxo.vout), height);
+                }
+            }
+        }
+
+        for utxo in utxos.iter_mut().filter(|e| e.height.is_none()) {
+            utxo.height = heights.get(&utxo.outpoint()).cloned();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for UtxoDetectiveProvider {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        let utxos = self
+            .api
+            .utxos(&query.account_public_key, query.amount)
+            .await?
+            .iter()
+            .map(|e| {
+                Ok(UTXO {
+                    txid: e.txid.clone(),
+                    vout: e.vout,
+                    satoshis: parse_field("utxo detective", "satoshis", &e.satoshis)?,
+                    path: parse_field("utxo detective", "path", &e.path)?,
+                    script: None,
+                    height: None,
+                    script_type: None,
+                })
+            })
+            .collect::<Result<Vec<UTXO>, TwetchError>>()?;
+
+        Ok(utxos)
+    }
+}
+
+fn parse_field<T: FromStr>(service: &str, field: &str, value: &str) -> Result<T, TwetchError> {
+    match value.parse::<T>() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(TwetchError::api(
+            service,
+            vec![format!("invalid {} '{}'", field, value)],
+        )),
+    }
+}
+
+/// Utxos of the account address with their block height.
+pub struct UtxoDetectiveAddressProvider {
+    api: UtxoDetectiveApi,
+}
+
+impl UtxoDetectiveAddressProvider {
+    pub fn new(url: String) -> UtxoDetectiveAddressProvider {
+        UtxoDetectiveAddressProvider {
+            api: UtxoDetectiveApi::new(url),
+        }
+    }
+}
+
+impl Default for UtxoDetectiveAddressProvider {
+    fn default() -> UtxoDetectiveAddressProvider {
+        UtxoDetectiveAddressProvider::new(SdkConfig::current().utxo_detective_url)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for UtxoDetectiveAddressProvider {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        let address = query.account_public_key.to_p2pkh_address()?.to_string()?;
+        let utxos = self
+            .api
+            .utxos_by_address(&address)
+            .await?
+            .iter()
+            .map(|e| {
+                Ok(UTXO {
+                    txid: e.txid.clone(),
+                    vout: e.vout,
+                    satoshis: parse_field("utxo detective", "satoshis", &e.satoshis)?,
+                    path: -1,
+                    script: None,
+                    height: e.block_height.filter(|v| *v > 0),
+                    script_type: None,
+                })
+            })
+            .collect::<Result<Vec<UTXO>, TwetchError>>()?;
+
+        Ok(utxos)
+    }
+}
+
+/// Paymail path utxos of the account public key as indexed by metasync.
+pub struct MetasyncProvider {
+    api: MetasyncApi,
+}
+
+impl MetasyncProvider {
+    pub fn new(url: String) -> MetasyncProvider {
+        MetasyncProvider {
+            api: MetasyncApi::new(url),
+        }
+    }
+}
+
+impl Default for MetasyncProvider {
+    fn default() -> MetasyncProvider {
+        MetasyncProvider::new(SdkConfig::current().metasync_url)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for MetasyncProvider {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        let utxos = self
+            .api
+            .utxos(&query.account_public_key, &query.network)
+            .await?
+            .iter()
+            .map(|e| {
+                Ok(UTXO {
+                    txid: e.txid.clone(),
+                    vout: e.vout,
+                    satoshis: parse_field("metasync", "satoshis", &e.satoshis)?,
+                    path: parse_field("metasync", "path", &e.path)?,
+                    script: None,
+                    height: None,
+                    script_type: None,
+                })
+            })
+            .collect::<Result<Vec<UTXO>, TwetchError>>()?;
+
+        Ok(utxos)
+    }
+}
+
+/// Utxos of the account address.
+pub struct WhatsOnChainProvider {
+    api: WhatsOnChainApi,
+}
+
+impl WhatsOnChainProvider {
+    pub fn new(url: String) -> WhatsOnChainProvider {
+        WhatsOnChainProvider {
+            api: WhatsOnChainApi::new(url),
+        }
+    }
+}
+
+impl WhatsOnChainProvider {
+    pub async fn address_utxos(
+        &self,
+        address: &P2PKHAddress,
+        network: &Networks,
+    ) -> Result<Vec<UTXO>> {
+        let utxos = self
+            .api
+            .utxos(address, network)
+            .await?
+            .iter()
+            .map(|e| UTXO {
+                txid: e.tx_hash.clone(),
+                vout: e.tx_pos,
+                satoshis: e.value,
+                path: -1,
+                script: None,
+                height: e.height.filter(|v| *v > 0),
+                script_type: None,
+            })
+            .collect();
+
+        Ok(utxos)
+    }
+}
+
+impl Default for WhatsOnChainProvider {
+    fn default() -> WhatsOnChainProvider {
+        WhatsOnChainProvider::new(SdkConfig::current().whatsonchain_url)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for WhatsOnChainProvider {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        let address = query.account_public_key.to_p2pkh_address()?;
+        self.address_utxos(&address, &query.network).await
+    }
+}
+
+/// Asks every provider in order and merges the results. An outpoint returned
+/// twice keeps the first answer, with the block height of a later one if the
+/// first didn't know it.
+pub struct CompositeProvider {
+    providers: Vec<Arc<dyn UtxoProvider>>,
+}
+
+impl CompositeProvider {
+    pub fn new(providers: Vec<Arc<dyn UtxoProvider>>) -> CompositeProvider {
+        CompositeProvider { providers }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for CompositeProvider {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        let mut utxos: Vec<UTXO> = Vec::new();
+        let mut seen: HashMap<Outpoint, usize> = HashMap::new();
+
+        for provider in &self.providers {
+            for utxo in provider.utxos(query).await? {
+                match seen.get(&utxo.outpoint()) {
+                    Some(i) => {
+                        if utxos[*i].height.is_none() {
+                            utxos[*i].height = utxo.height;
+                        }
+                    }
+                    None => {
+                        seen.insert(utxo.outpoint(), utxos.len());
+                        utxos.push(utxo);
+                    }
+                }
+            }
+        }
+
+        Ok(utxos)
+    }
+}
+
+/// Utxo detective on BSV, metasync and WhatsOnChain on other networks. Combine
+/// it with `UtxoDetectiveAddressProvider` to also fund from the account address.
+pub struct NetworkUtxoProvider {
+    config: SdkConfig,
+}
+
+impl NetworkUtxoProvider {
+    pub fn new(config: SdkConfig) -> NetworkUtxoProvider {
+        NetworkUtxoProvider { config }
+    }
+}
+
+impl Default for NetworkUtxoProvider {
+    fn default() -> NetworkUtxoProvider {
+        NetworkUtxoProvider::new(SdkConfig::current())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for NetworkUtxoProvider {
+    async fn utxos(&self, query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        let config = &self.config;
+        match query.network {
+            Networks::BSV => {
+                UtxoDetectiveProvider::new(config.utxo_detective_url.clone())
+                    .utxos(query)
+                    .await
+            }
+            _ => {
+                CompositeProvider::new(vec![
+                    Arc::new(MetasyncProvider::new(config.metasync_url.clone())),
+                    Arc::new(WhatsOnChainProvider::new(config.whatsonchain_url.clone())),
+                ])
+                .utxos(query)
+                .await
+            }
+        }
+    }
+}
+
+/// Hands out a fixed set of utxos, for tests and wallets that track their own.
+#[derive(Default)]
+pub struct StaticUtxoProvider {
+    pub utxos: Vec<UTXO>,
+}
+
+impl StaticUtxoProvider {
+    pub fn new(utxos: Vec<UTXO>) -> StaticUtxoProvider {
+        StaticUtxoProvider { utxos }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl UtxoProvider for StaticUtxoProvider {
+    async fn utxos(&self, _query: &UtxoQuery) -> Result<Vec<UTXO>> {
+        Ok(self.utxos.clone())
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{constants, now_ms, BuiltTx, Outpoint, UtxoDetectiveApi, WalletOutput, UTXO};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredUtxo {
+    pub utxo: UTXO,
+    pub confirmed: bool,
+    pub spent: bool,
+    /// Spending txid when known, spends reported by the mempool alone don't carry one.
+    #[serde(default)]
+    pub spent_by: Option<String>,
+    /// When the wallet marked it spent, in ms since the epoch.
+    #[serde(default)]
+    pub spent_at: Option<u64>,
+}
+
+/// Local view of the wallet's utxos. Outputs of our own transactions show up
+/// and their inputs disappear as soon as they are broadcast, instead of once
+/// the indexer catches up, so transactions can be chained.
+pub trait UtxoStore: Send + Sync {
+    fn all(&self) -> Result<Vec<StoredUtxo>>;
+
+    /// Inserts the entries, replacing any with the same outpoint.
+    fn put(&self, utxos: Vec<StoredUtxo>) -> Result<()>;
+
+    fn remove(&self, outpoints: &[Outpoint]) -> Result<()>;
+
+    fn get(&self, outpoint: &Outpoint) -> Result<Option<StoredUtxo>> {
+        Ok(self
+            .all()?
+            .into_iter()
+            .find(|e| e.utxo.outpoint() == *outpoint))
+    }
+
+    fn unspent(&self) -> Result<Vec<UTXO>> {
+        Ok(self
+            .all()?
+            .into_iter()
+            .filter(|e| !e.spent)
+            .map(|e| e.utxo)
+            .collect())
+    }
+
+    /// Marks the utxos spent, keeping ones the store hasn't seen yet so an
+    /// indexer lagging behind can't hand them out again.
+    fn mark_spent(&self, utxos: &[UTXO], spent_by: Option<&str>) -> Result<()> {
+        let mut entries = Vec::new();
+
+        for utxo in utxos {
+            let stored = self.get(&utxo.outpoint())?;
+            entries.push(StoredUtxo {
+                utxo: stored
+                    .as_ref()
+                    .map(|e| e.utxo.clone())
+                    .unwrap_or(utxo.clone()),
+                confirmed: stored.map(|e| e.confirmed).unwrap_or(false),
+                spent: true,
+                spent_by: spent_by.map(|e| e.to_string()),
+                spent_at: Some(now_ms()),
+            });
+        }
+
+        self.put(entries)
+    }
+
+    /// Spends the inputs of a broadcast transaction and adds its change and
+    /// other outputs to the wallet's keys.
+    fn record(&self, built: &BuiltTx) -> Result<()> {
+        let mut inputs = Vec::new();
+        for i in 0..built.tx.get_ninputs() {
+            let tx_in = match built.tx.get_input(i) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            inputs.push(match built.utxos.get(i).cloned().flatten() {
+                Some(v) => v,
+                None => UTXO {
+                    txid: tx_in.get_prev_tx_id_hex(None),
+                    vout: tx_in.get_vout(),
+                    satoshis: tx_in.get_satoshis().unwrap_or(0),
+                    path: -1,
+                    script: None,
+                    height: None,
+                    script_type: None,
+                },
+            });
+        }
+        self.mark_spent(&inputs, Some(&built.txid))?;
+
+        // change to our own keys is always the last output
+        let mut outputs = built.wallet_outputs.clone();
+        if let Some(path) = built.change_path {
+            outputs.push(WalletOutput {
+                vout: built.tx.get_noutputs() as u32 - 1,
+                path,
+            });
+        }
+
+        let mut entries = Vec::new();
+        for output in outputs {
+            let tx_out = match built.tx.get_output(output.vout as usize) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            entries.push(StoredUtxo {
+                utxo: UTXO {
+                    txid: built.txid.clone(),
+                    vout: output.vout,
+                    satoshis: tx_out.get_satoshis(),
+                    path: output.path,
+                    script: Some(tx_out.get_script_pub_key()),
+                    height: None,
+                    script_type: None,
+                },
+                confirmed: false,
+                spent: false,
+                spent_by: None,
+                spent_at: None,
+            });
+        }
+
+        self.put(entries)
+    }
+
+    /// Remote utxos without the ones we've spent, plus the unspent ones the
+    /// remote doesn't know about yet.
+    fn merge(&self, remote: Vec<UTXO>) -> Result<Vec<UTXO>> {
+        let stored = self.all()?;
+        let spent: HashSet<Outpoint> = stored
+            .iter()
+            .filter(|e| e.spent)
+            .map(|e| e.utxo.outpoint())
+            .collect();
+
+        let mut utxos: Vec<UTXO> = remote
+            .into_iter()
+            .filter(|e| !spent.contains(&e.outpoint()))
+            .collect();
+        let known: HashSet<Outpoint> = utxos.iter().map(|e| e.outpoint()).collect();
+
+        utxos.extend(
+            stored
+                .into_iter()
+                .filter(|e| !e.spent && !known.contains(&e.utxo.outpoint()))
+                .map(|e| e.utxo),
+        );
+
+        Ok(utxos)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryUtxoStore {
+    utxos: Mutex<HashMap<Outpoint, StoredUtxo>>,
+}
+
+impl MemoryUtxoStore {
+    pub fn new() -> MemoryUtxoStore {
+        MemoryUtxoStore::default()
+    }
+}
+
+impl UtxoStore for MemoryUtxoStore {
+    fn all(&self) -> Result<Vec<StoredUtxo>> {
+        Ok(self.utxos.lock().unwrap().values().cloned().collect())
+    }
+
+    fn put(&self, utxos: Vec<StoredUtxo>) -> Result<()> {
+        let mut stored = self.utxos.lock().unwrap();
+        for utxo in utxos {
+            stored.insert(utxo.utxo.outpoint(), utxo);
+        }
+        Ok(())
+    }
+
+    fn remove(&self, outpoints: &[Outpoint]) -> Result<()> {
+        let mut stored = self.utxos.lock().unwrap();
+        for outpoint in outpoints {
+            stored.remove(outpoint);
+        }
+        Ok(())
+    }
+
+    fn get(&self, outpoint: &Outpoint) -> Result<Option<StoredUtxo>> {
+        Ok(self.utxos.lock().unwrap().get(outpoint).cloned())
+    }
+}
+
+/// Utxos as a JSON file, rewritten on every change.
+pub struct FileUtxoStore {
+    path: PathBuf,
+    memory: MemoryUtxoStore,
+}
+
+impl FileUtxoStore {
+    pub fn new(path: PathBuf) -> Result<FileUtxoStore> {
+        let memory = MemoryUtxoStore::new();
+
+        if path.exists() {
+            let utxos: Vec<StoredUtxo> = serde_json::from_slice(&std::fs::read(&path)?)?;
+            memory.put(utxos)?;
+        }
+
+        Ok(FileUtxoStore { path, memory })
+    }
+
+    fn save(&self) -> Result<()> {
+        Ok(std::fs::write(
+            &self.path,
+            serde_json::to_vec(&self.memory.all()?)?,
+        )?)
+    }
+}
+
+impl UtxoStore for FileUtxoStore {
+    fn all(&self) -> Result<Vec<StoredUtxo>> {
+        self.memory.all()
+    }
+
+    fn put(&self, utxos: Vec<StoredUtxo>) -> Result<()> {
+        self.memory.put(utxos)?;
+        self.save()
+    }
+
+    fn remove(&self, outpoints: &[Outpoint]) -> Result<()> {
+        self.memory.remove(outpoints)?;
+        self.save()
+    }
+
+    fn get(&self, outpoint: &Outpoint) -> Result<Option<StoredUtxo>> {
+        self.memory.get(outpoint)
+    }
+}
+
+/// Catches the store up with utxo detective. Entries whose spend was mined
+/// are dropped, spends seen in the mempool are marked and new utxos of
+/// `address` are added. Entries spent locally are spendable again when the
+/// indexer still hasn't seen their spend `UNSEEN_SPEND_TIMEOUT_MS` later.
+pub async fn reconcile_utxo_store(
+    store: &dyn UtxoStore,
+    api: &UtxoDetectiveApi,
+    address: Option<String>,
+) -> Result<()> {
+    let stored = store.all()?;
+
+    let outpoints = stored
+        .iter()
+        .map(|e| e.utxo.outpoint().to_bytes())
+        .collect::<Result<Vec<_>>>()?;
+    let spends = if outpoints.is_empty() {
+        Vec::new()
+    } else {
+        api.spends_by_outpoint(outpoints).await?
+    };
+
+    let now = now_ms();
+    let mut mined = Vec::new();
+    let mut spent = Vec::new();
+    let mut unspent = Vec::new();
+    let mut unseen = Vec::new();
+    for (i, entry) in stored.into_iter().enumerate() {
+        match spends.get(i).cloned().flatten() {
+            Some((_, height)) if height > 0 => mined.push(entry.utxo.outpoint()),
+            Some((txid, _)) => spent.push(StoredUtxo {
+                spent: true,
+                spent_by: Some(hex::encode(txid)),
+                ..entry
+            }),
+            None if !entry.spent => unspent.push(entry),
+            None if entry.spent_at.unwrap_or(0) + constants::UNSEEN_SPEND_TIMEOUT_MS <= now => {
+                unseen.push(entry)
+            }
+            None => {}
+        }
+    }
+    store.remove(&mined)?;
+    store.put(spent)?;
+
+    let outpoints = unspent
+        .iter()
+        .chain(unseen.iter())
+        .map(|e| e.utxo.outpoint().to_bytes())
+        .collect::<Result<Vec<_>>>()?;
+    let (in_mempool, new_utxos) = api.mempool_check(outpoints, address).await?;
+
+    let spent_utxos: Vec<UTXO> = unspent
+        .iter()
+        .zip(in_mempool.iter())
+        .filter(|(_, spent)| **spent)
+        .map(|(e, _)| e.utxo.clone())
+        .collect();
+    store.mark_spent(&spent_utxos, None)?;
+
+    // nowhere to be seen, the spending transaction was dropped
+    let unspent_again: Vec<StoredUtxo> = unseen
+        .into_iter()
+        .zip(in_mempool.iter().skip(unspent.len()))
+        .filter(|(_, spent)| !**spent)
+        .map(|(e, _)| StoredUtxo {
+            spent: false,
+            spent_by: None,
+            spent_at: None,
+            ..e
+        })
+        .collect();
+    store.put(unspent_again)?;
+
+    let mut added = Vec::new();
+    for e in new_utxos {
+        let outpoint = Outpoint::new(&e.txid, e.vout);
+        let height = e.block_height.filter(|v| *v > 0);
+
+        let entry = match store.get(&outpoint)? {
+            Some(v) => StoredUtxo {
+                confirmed: v.confirmed || height.is_some(),
+                utxo: UTXO { height, ..v.utxo },
+                ..v
+            },
+            None => StoredUtxo {
+                utxo: UTXO {
+                    txid: e.txid.clone(),
+                    vout: e.vout,
+                    satoshis: e.satoshis.parse::<u64>()?,
+                    path: -1,
+                    script: None,
+                    height,
+                    script_type: None,
+                },
+                confirmed: height.is_some(),
+                spent: false,
+                spent_by: None,
+                spent_at: None,
+            },
+        };
+        added.push(entry);
+    }
+    store.put(added)
+}
@@ -0,0 +1,234 @@
+mod common;
+
+#[cfg(test)]
+mod arc_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use bsv::Transaction;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use twetch_sdk::{
+        ArcApi, ArcBroadcaster, ArcSubmitOptions, ArcTxStatus, BroadcastStatus, Broadcaster,
+        ExtendedFormat, MultiBroadcaster, TxBuilder, TxBuilderOutput, Wallet, UTXO,
+    };
+
+    const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
+
+    // version 1, one input spending 11..11:0 with OP_TRUE, one OP_RETURN output
+    fn raw_tx() -> Vec<u8> {
+        let mut raw = hex::decode("0100000001").unwrap();
+        raw.extend_from_slice(&[0x11; 32]);
+        raw.extend_from_slice(&hex::decode("000000000151ffffffff01").unwrap());
+        raw.extend_from_slice(&hex::decode("0000000000000000016a00000000").unwrap());
+        raw
+    }
+
+    #[test]
+    fn extended_format() -> Result<()> {
+        let raw = raw_tx();
+        let ef = ExtendedFormat::from_raw(&raw, &[(1000, vec![0x76, 0xa9])])?;
+
+        let mut expected = hex::decode("010000000000000000ef01").unwrap();
+        expected.extend_from_slice(&raw[5..47]);
+        expected.extend_from_slice(&hex::decode("e803000000000000").unwrap());
+        expected.extend_from_slice(&[0x02, 0x76, 0xa9]);
+        expected.extend_from_slice(&raw[47..]);
+
+        assert_eq!(ef, expected);
+        assert!(ExtendedFormat::is_extended(&ef));
+        assert!(!ExtendedFormat::is_extended(&raw));
+        assert!(ExtendedFormat::from_raw(&raw, &[]).is_err());
+
+        // truncated in the middle of the input
+        let prevouts = [(1000, vec![0x76, 0xa9])];
+        let err = ExtendedFormat::from_raw(&raw[..40], &prevouts).unwrap_err();
+        assert!(err.to_string().starts_with("EF Error"));
+
+        // an unlocking script length of u64::MAX
+        let mut huge = raw[..41].to_vec();
+        huge.push(0xff);
+        huge.extend_from_slice(&[0xff; 8]);
+        huge.extend_from_slice(&raw[42..]);
+        let err = ExtendedFormat::from_raw(&huge, &prevouts).unwrap_err();
+        assert!(err.to_string().starts_with("EF Error"));
+
+        Ok(())
+    }
+
+    fn status(value: Value) -> BroadcastStatus {
+        serde_json::from_value::<twetch_sdk::ArcTxResponse>(value)
+            .unwrap()
+            .status()
+    }
+
+    #[test]
+    fn statuses() {
+        assert_eq!(
+            status(json!({ "txid": "a", "txStatus": "SEEN_ON_NETWORK", "status": 200 })),
+            BroadcastStatus::Accepted
+        );
+        assert_eq!(
+            status(json!({ "txStatus": "MINED", "blockHeight": 800000 })),
+            BroadcastStatus::Accepted
+        );
+        assert_eq!(
+            status(json!({ "txStatus": "RECEIVED" })),
+            BroadcastStatus::Accepted
+        );
+        assert_eq!(
+            status(json!({ "txStatus": "REJECTED", "extraInfo": "txn-mempool-conflict" })),
+            BroadcastStatus::Rejected("txn-mempool-conflict".to_string())
+        );
+        assert!(
+            !status(json!({ "txStatus": "DOUBLE_SPEND_ATTEMPTED", "competingTxs": ["b"] }))
+                .is_success()
+        );
+        assert_eq!(
+            status(json!({ "status": 465, "title": "Fee too low" })),
+            BroadcastStatus::Rejected("Fee too low".to_string())
+        );
+        assert_eq!(
+            status(json!({ "status": 461, "title": "Malformed transaction" })),
+            BroadcastStatus::Rejected("Malformed transaction".to_string())
+        );
+        assert_eq!(
+            status(json!({ "status": 503, "title": "Unavailable" })),
+            BroadcastStatus::Transient("Unavailable".to_string())
+        );
+        for code in [401, 403, 404, 408, 429] {
+            assert_eq!(
+                status(json!({ "status": code, "title": "Not now" })),
+                BroadcastStatus::Transient("Not now".to_string())
+            );
+        }
+        assert_eq!(
+            status(json!({ "txStatus": "SOMETHING_NEW" })),
+            BroadcastStatus::Transient("".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn submit() -> Result<()> {
+        let url = serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+            match (request.method.as_str(), request.path.as_str()) {
+                ("POST", "/v1/tx") => {
+                    assert_eq!(request.header("authorization").unwrap(), "Bearer token");
+                    assert_eq!(
+                        request.header("x-callbackurl").unwrap(),
+                        "https://twetch.com/arc"
+                    );
+                    assert_eq!(request.header("x-waitfor").unwrap(), "SEEN_ON_NETWORK");
+                    assert_eq!(body["rawTx"], "0102");
+                    StandInResponse::json(
+                        200,
+                        json!({ "txid": "a", "txStatus": "SEEN_ON_NETWORK", "status": 200 }),
+                    )
+                }
+                ("POST", "/v1/txs") => StandInResponse::json(
+                    200,
+                    json!([
+                        { "txid": "a", "txStatus": "STORED" },
+                        { "txid": "b", "txStatus": "REJECTED", "extraInfo": "bad-txns" }
+                    ]),
+                ),
+                ("GET", "/v1/tx/a") => StandInResponse::json(
+                    200,
+                    json!({ "txid": "a", "txStatus": "MINED", "blockHeight": 800000 }),
+                ),
+                _ => StandInResponse::json(404, json!({ "status": 404, "title": "Not found" })),
+            }
+        })
+        .await;
+
+        let api = ArcApi::new(url, Some("token".to_string()));
+        let options = ArcSubmitOptions {
+            callback_url: Some("https://twetch.com/arc".to_string()),
+            wait_for: Some(ArcTxStatus::SeenOnNetwork),
+            ..Default::default()
+        };
+
+        let res = api.submit_tx(&[1, 2], &options).await?;
+        assert_eq!(res.tx_status, Some(ArcTxStatus::SeenOnNetwork));
+
+        let res = api
+            .submit_txs(&[vec![1], vec![2]], &ArcSubmitOptions::default())
+            .await?;
+        let statuses: Vec<bool> = res.iter().map(|e| e.status().is_success()).collect();
+        assert_eq!(statuses, vec![true, false]);
+
+        let res = api.tx_status("a").await?;
+        assert_eq!(res.tx_status, Some(ArcTxStatus::Mined));
+        assert_eq!(res.block_height, Some(800000));
+
+        let res = api.tx_status("b").await?;
+        assert_eq!(res.status, Some(404));
+        assert!(!res.status().is_success());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn broadcast_extended() -> Result<()> {
+        let url = serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+            let rawtx = hex::decode(body["rawTx"].as_str().unwrap_or_default()).unwrap();
+            assert!(ExtendedFormat::is_extended(&rawtx));
+            StandInResponse::json(200, json!({ "txid": "a", "txStatus": "SEEN_ON_NETWORK" }))
+        })
+        .await;
+
+        let wallet = Wallet::new(SEED.to_string());
+        let built = wallet
+            .build_tx(&TxBuilder {
+                outputs: vec![TxBuilderOutput {
+                    sats: 500,
+                    address: Some(wallet.account_address()?.to_string()?),
+                    to: None,
+                    script: None,
+                    args: None,
+                    encrypt_args: None,
+                }],
+                auto_fund: true,
+                utxos: Some(vec![UTXO {
+                    txid: "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e"
+                        .to_string(),
+                    vout: 0,
+                    satoshis: 1000,
+                    path: -1,
+                    script: None,
+                    height: None,
+                    script_type: None,
+                }]),
+                offline: true,
+                ..Default::default()
+            })
+            .await?;
+        assert!(built.signed);
+
+        let res = ArcBroadcaster::new(url, None).broadcast(&built.tx).await;
+        assert_eq!(res.status, BroadcastStatus::Accepted);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rate_limited_fails_over() {
+        let limited = serve(|_| async move {
+            StandInResponse::json(429, json!({ "status": 429, "title": "Too Many Requests" }))
+        })
+        .await;
+        let healthy = serve(|_| async move {
+            StandInResponse::json(200, json!({ "txid": "a", "txStatus": "SEEN_ON_NETWORK" }))
+        })
+        .await;
+
+        let failover = MultiBroadcaster::failover(vec![
+            Arc::new(ArcBroadcaster::new(limited, None)),
+            Arc::new(ArcBroadcaster::new(healthy.clone(), None)),
+        ]);
+        let res = failover.broadcast(&Transaction::new(1, 0)).await;
+        assert_eq!(res.status, BroadcastStatus::Accepted);
+        assert_eq!(res.broadcaster, format!("arc {}", healthy));
+    }
+}
@@ -0,0 +1,238 @@
+mod common;
+
+#[cfg(test)]
+mod broadcaster_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use bsv::Transaction;
+    use serde_json::{json, Value};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tokio::sync::Barrier;
+    use twetch_sdk::{
+        ArcBroadcaster, BroadcastResult, BroadcastStatus, Broadcaster, MapiApi, MultiBroadcaster,
+        RPCClient,
+    };
+
+    #[test]
+    fn already_known() {
+        assert_eq!(
+            BroadcastStatus::from_error("257: txn-already-known"),
+            BroadcastStatus::AlreadyKnown
+        );
+        assert_eq!(
+            BroadcastStatus::from_error("Transaction already in the mempool"),
+            BroadcastStatus::AlreadyKnown
+        );
+        assert_eq!(
+            BroadcastStatus::from_error("16: mandatory-script-verify-flag-failed"),
+            BroadcastStatus::Rejected("16: mandatory-script-verify-flag-failed".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn mapi() -> Result<()> {
+        let url = serve(|request| async move {
+            let (result, description) = match request.body[0] {
+                1 => ("success", ""),
+                2 => ("failure", "Transaction already known"),
+                _ => ("failure", "Missing inputs"),
+            };
+            let payload = json!({
+                "txid": "txid",
+                "returnResult": result,
+                "resultDescription": description,
+            });
+            StandInResponse::json(200, json!({ "payload": payload.to_string() }))
+        })
+        .await;
+
+        let api = MapiApi::new(url, "token".to_string());
+        let statuses: Vec<BroadcastStatus> = vec![
+            api.submit_rawtx(&vec![1]).await?.status(),
+            api.submit_rawtx(&vec![2]).await?.status(),
+            api.submit_rawtx(&vec![3]).await?.status(),
+        ];
+        assert_eq!(
+            statuses,
+            vec![
+                BroadcastStatus::Accepted,
+                BroadcastStatus::AlreadyKnown,
+                BroadcastStatus::Rejected("Missing inputs".to_string())
+            ]
+        );
+
+        assert!(api.broadcast_rawtx(&vec![2]).await?.success);
+        assert!(!api.broadcast_rawtx(&vec![3]).await?.success);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc() -> Result<()> {
+        let url = serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            match body["params"][0].as_str().unwrap() {
+                "01" => StandInResponse::json(200, json!({ "result": "txid", "id": "420" })),
+                _ => StandInResponse::json(
+                    500,
+                    json!({
+                        "result": null,
+                        "id": "420",
+                        "error": { "code": -26, "message": "257: txn-already-known" }
+                    }),
+                ),
+            }
+        })
+        .await;
+
+        let client: RPCClient = serde_json::from_value(json!({
+            "host": url,
+            "user": "user",
+            "password": "password",
+        }))?;
+
+        let res = client.send_rawtransaction(&vec![1]).await?;
+        assert_eq!(res.status(), BroadcastStatus::Accepted);
+
+        let res = client.send_rawtransaction(&vec![2]).await?;
+        assert_eq!(res.status(), BroadcastStatus::AlreadyKnown);
+        assert!(client.broadcast_rawtx(&vec![2]).await?.success);
+
+        Ok(())
+    }
+
+    struct Fixed {
+        status: BroadcastStatus,
+        hits: AtomicUsize,
+    }
+
+    impl Fixed {
+        fn new(status: BroadcastStatus) -> Arc<Fixed> {
+            Arc::new(Fixed {
+                status,
+                hits: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Broadcaster for Fixed {
+        fn name(&self) -> String {
+            format!("{:?}", self.status)
+        }
+
+        async fn broadcast(&self, _tx: &Transaction) -> BroadcastResult {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+            BroadcastResult {
+                broadcaster: self.name(),
+                txid: None,
+                status: self.status.clone(),
+                response: Value::Null,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn failover_and_multicast() {
+        let tx = Transaction::new(1, 0);
+        let down = Fixed::new(BroadcastStatus::Transient("timed out".to_string()));
+        let known = Fixed::new(BroadcastStatus::AlreadyKnown);
+        let rejected = Fixed::new(BroadcastStatus::Rejected("Missing inputs".to_string()));
+
+        let failover =
+            MultiBroadcaster::failover(vec![down.clone(), known.clone(), rejected.clone()]);
+        let res = failover.broadcast(&tx).await;
+        assert_eq!(res.status, BroadcastStatus::AlreadyKnown);
+        assert_eq!(rejected.hits.load(Ordering::SeqCst), 0);
+
+        let multicast =
+            MultiBroadcaster::multicast(vec![rejected.clone(), known.clone(), down.clone()]);
+        let results = multicast.broadcast_all(&tx).await;
+        assert_eq!(results.len(), 3);
+        assert!(multicast.broadcast(&tx).await.is_success());
+
+        let res = MultiBroadcaster::failover(vec![down.clone(), rejected.clone()])
+            .broadcast(&tx)
+            .await;
+        assert_eq!(
+            res.status,
+            BroadcastStatus::Rejected("Missing inputs".to_string())
+        );
+
+        // a rejection is final, only transient failures fail over
+        let hits = known.hits.load(Ordering::SeqCst);
+        let failover = MultiBroadcaster::failover(vec![rejected.clone(), known.clone()]);
+        assert_eq!(failover.broadcast_all(&tx).await.len(), 1);
+        assert_eq!(known.hits.load(Ordering::SeqCst), hits);
+    }
+
+    #[tokio::test]
+    async fn unauthorized_arc_fails_over() {
+        let url = serve(|_| async move {
+            StandInResponse::json(401, json!({ "status": 401, "title": "Unauthorized" }))
+        })
+        .await;
+        let known = Fixed::new(BroadcastStatus::AlreadyKnown);
+
+        // a misconfigured backend says nothing about the transaction
+        let failover = MultiBroadcaster::failover(vec![
+            Arc::new(ArcBroadcaster::new(url, None)),
+            known.clone(),
+        ]);
+        let results = failover.broadcast_all(&Transaction::new(1, 0)).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].status,
+            BroadcastStatus::Transient("Unauthorized".to_string())
+        );
+        assert_eq!(results[1].status, BroadcastStatus::AlreadyKnown);
+        assert_eq!(known.hits.load(Ordering::SeqCst), 1);
+    }
+
+    /// Answers once every other `Gated` broadcaster was asked too.
+    struct Gated {
+        barrier: Arc<Barrier>,
+    }
+
+    #[async_trait]
+    impl Broadcaster for Gated {
+        fn name(&self) -> String {
+            "gated".to_string()
+        }
+
+        async fn broadcast(&self, _tx: &Transaction) -> BroadcastResult {
+            self.barrier.wait().await;
+            BroadcastResult {
+                broadcaster: self.name(),
+                txid: None,
+                status: BroadcastStatus::Accepted,
+                response: Value::Null,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn multicast_at_once() {
+        let barrier = Arc::new(Barrier::new(3));
+        let broadcasters: Vec<Arc<dyn Broadcaster>> = (0..3)
+            .map(|_| {
+                Arc::new(Gated {
+                    barrier: barrier.clone(),
+                }) as Arc<dyn Broadcaster>
+            })
+            .collect();
+
+        let multicast = MultiBroadcaster::multicast(broadcasters);
+        let results = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            multicast.broadcast_all(&Transaction::new(1, 0)),
+        )
+        .await
+        .expect("broadcasters were asked one after the other");
+        assert_eq!(results.len(), 3);
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod coin_selection_tests {
+    use anyhow::Result;
+    use twetch_sdk::{
+        BranchAndBound, CoinSelection, CoinSelector, LargestFirst, MinimiseInputs, OldestFirst,
+        UTXO,
+    };
+
+    fn utxo(vout: u32, satoshis: u64, height: Option<i64>) -> UTXO {
+        UTXO {
+            txid: "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e".to_string(),
+            vout,
+            satoshis,
+            path: -1,
+            script: None,
+            height,
+            script_type: None,
+        }
+    }
+
+    // 10 sats for the transaction plus 5 per input
+    fn fee(selected: &[UTXO]) -> Result<u64> {
+        Ok(10 + 5 * selected.len() as u64)
+    }
+
+    fn vouts(selected: &[UTXO]) -> Vec<u32> {
+        let mut vouts: Vec<u32> = selected.iter().map(|e| e.vout).collect();
+        vouts.sort();
+        vouts
+    }
+
+    fn available() -> Vec<UTXO> {
+        vec![
+            utxo(0, 5000, Some(700000)),
+            utxo(1, 20000, None),
+            utxo(2, 1200, Some(650000)),
+            utxo(3, 3000, Some(690000)),
+        ]
+    }
+
+    #[test]
+    fn largest_first() -> Result<()> {
+        let selected = LargestFirst.select(&available(), 22000, &fee)?;
+        assert_eq!(vouts(&selected), vec![0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn oldest_first() -> Result<()> {
+        let selected = OldestFirst.select(&available(), 4000, &fee)?;
+        assert_eq!(vouts(&selected), vec![2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn minimise_inputs() -> Result<()> {
+        let selected = MinimiseInputs.select(&available(), 4000, &fee)?;
+        assert_eq!(vouts(&selected), vec![0]);
+
+        let selected = MinimiseInputs.select(&available(), 24000, &fee)?;
+        assert_eq!(vouts(&selected), vec![0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn branch_and_bound() -> Result<()> {
+        // 5000 + 3000 pays 7970 plus 20 sats of fees with nothing left over
+        let selected = BranchAndBound::default().select(&available(), 7970, &fee)?;
+        assert_eq!(vouts(&selected), vec![0, 3]);
+
+        // no exact match, falls back to largest-first
+        let selected = CoinSelection::BranchAndBound.select(&available(), 21000, &fee)?;
+        assert_eq!(vouts(&selected), vec![0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn insufficient_balance() {
+        assert!(LargestFirst.select(&available(), 30000, &fee).is_err());
+        assert!(CoinSelection::OldestFirst
+            .select(&available(), 30000, &fee)
+            .is_err());
+    }
+}
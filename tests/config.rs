@@ -0,0 +1,103 @@
+mod common;
+
+#[cfg(test)]
+mod config_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::{json, Value};
+    use twetch_sdk::{constants, Api, Networks, SdkConfig, Wallet};
+
+    const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
+
+    #[test]
+    fn partial_config() -> Result<()> {
+        let config: SdkConfig = serde_json::from_value(json!({
+            "api_url": "http://localhost:3000",
+            "network": "TBSV",
+        }))?;
+
+        assert_eq!(config.api_url, "http://localhost:3000");
+        assert_eq!(config.network, Networks::TBSV);
+        assert_eq!(config.polynym_url, constants::POLYNYM_URL);
+        assert_eq!(config.client_identifier, constants::CLIENT_IDENTIFIER);
+
+        let local = SdkConfig::local("http://localhost:3000");
+        assert_eq!(local.utxo_detective_url, "http://localhost:3000");
+        assert_eq!(local.network, Networks::BSV);
+        assert_eq!(SdkConfig::testnet().network, Networks::TBSV);
+
+        Ok(())
+    }
+
+    #[test]
+    fn wallet_config() {
+        let config = SdkConfig::local("http://localhost:3000");
+        let wallet = Wallet::new(SEED.to_string()).with_config(config.clone());
+
+        assert_eq!(wallet.config(), &config);
+        assert_eq!(wallet.clone().config(), &config);
+    }
+
+    #[test]
+    fn network() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string()).with_config(SdkConfig::testnet());
+        assert_eq!(wallet.network(), Networks::TBSV);
+        assert_eq!(Networks::default(), SdkConfig::current().network);
+
+        std::env::set_var("TWETCH_NETWORK", "TBSV");
+        assert_eq!(SdkConfig::from_env()?.network, Networks::TBSV);
+        std::env::set_var("TWETCH_NETWORK", "testnet");
+        assert!(SdkConfig::from_env().is_err());
+        std::env::remove_var("TWETCH_NETWORK");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_utxos_config() -> Result<()> {
+        let url = serve(|request| async move {
+            assert!(request.path.ends_with("/unspent"), "{}", request.path);
+            StandInResponse::json(
+                200,
+                json!([{ "tx_hash": "00".repeat(32), "tx_pos": 1, "value": 1000, "height": 0 }]),
+            )
+        })
+        .await;
+
+        let wallet = Wallet::new(SEED.to_string()).with_config(SdkConfig::local(&url));
+        let utxos = wallet.account_utxos(&Networks::BSV).await?;
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(wallet.account_balance(&Networks::BSV).await?, 1000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn payees_client_identifier() -> Result<()> {
+        let url = serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            assert_eq!(request.path, "/v1/payees");
+            assert_eq!(body["client_identifier"], "staging-client");
+
+            StandInResponse::json(
+                200,
+                json!({ "errors": [], "invoice": "invoice", "payees": [] }),
+            )
+        })
+        .await;
+
+        let config = SdkConfig {
+            client_identifier: "staging-client".to_string(),
+            ..SdkConfig::local(&url)
+        };
+        let api = Api::new(config.api_url, "token".to_string())
+            .with_client_identifier(config.client_identifier);
+
+        let res = api
+            .payees(&"twetch/post@0.0.1".to_string(), &vec![])
+            .await?;
+        assert_eq!(res.invoice, "invoice");
+
+        Ok(())
+    }
+}
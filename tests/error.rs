@@ -0,0 +1,183 @@
+mod common;
+
+#[cfg(test)]
+mod error_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::json;
+    use twetch_sdk::{
+        AuthToken, CoinSelector, FeePolicy, GraphqlApi, LargestFirst, RecipientError, TwetchError,
+        TxBuilder, TxBuilderOutput, TxlogApi, Wallet, UTXO,
+    };
+
+    fn utxo(vout: u32, satoshis: u64) -> UTXO {
+        UTXO {
+            txid: "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e".to_string(),
+            vout,
+            satoshis,
+            path: -1,
+            script: None,
+            height: None,
+            script_type: None,
+        }
+    }
+
+    #[test]
+    fn insufficient_funds() {
+        let fee = |selected: &[UTXO]| -> Result<u64> { Ok(10 + 5 * selected.len() as u64) };
+        let err = LargestFirst
+            .select(&vec![utxo(0, 1000), utxo(1, 2000)], 5000, &fee)
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<TwetchError>(),
+            Some(&TwetchError::InsufficientFunds {
+                required: 5020,
+                available: 3000
+            })
+        );
+    }
+
+    #[test]
+    fn auth() {
+        let err = AuthToken::new("twetch".to_string()).unwrap_err();
+        assert!(matches!(
+            TwetchError::from_error(&err),
+            Some(TwetchError::Auth(_))
+        ));
+    }
+
+    #[test]
+    fn from_error() {
+        let err: anyhow::Error = RecipientError::new("$twetch", "not found").into();
+        assert_eq!(
+            TwetchError::from_error(&err),
+            Some(TwetchError::Resolution(
+                "Recipient Error: failed to resolve '$twetch', not found".to_string()
+            ))
+        );
+
+        let err = anyhow::Error::from(TwetchError::Signing("no keys".to_string()))
+            .context("failed to sign");
+        assert_eq!(
+            TwetchError::from_error(&err),
+            Some(TwetchError::Signing("no keys".to_string()))
+        );
+
+        assert_eq!(TwetchError::from_error(&anyhow::anyhow!("twetch")), None);
+    }
+
+    #[tokio::test]
+    async fn graphql_errors() -> Result<()> {
+        let url = serve(|request| async move {
+            match request.path.as_str() {
+                "/errors" => StandInResponse::json(
+                    200,
+                    json!({ "data": null, "errors": [{ "message": "jwt expired" }] }),
+                ),
+                _ => StandInResponse::json(200, json!({})),
+            }
+        })
+        .await;
+
+        let api = GraphqlApi::new(format!("{}/errors", url), "token".to_string());
+        let err = api
+            .graphql("query { me }".to_string(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TwetchError>(),
+            Some(&TwetchError::Api {
+                service: "graphql".to_string(),
+                errors: vec!["jwt expired".to_string()]
+            })
+        );
+
+        let api = GraphqlApi::new(format!("{}/empty", url), "token".to_string());
+        let err = api
+            .graphql("query { me }".to_string(), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TwetchError>(),
+            Some(TwetchError::Api { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn http_status() -> Result<()> {
+        let url =
+            serve(|_| async move { StandInResponse::json(404, json!({ "error": "unknown tx" })) })
+                .await;
+
+        let err = TxlogApi::new(url.clone())
+            .satoshis(&"a".to_string(), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TwetchError>(),
+            Some(&TwetchError::Http {
+                url: Some(format!("{}/tx/a/0/satoshis", url)),
+                status: Some(404),
+                message: json!({ "error": "unknown tx" }).to_string(),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fee_too_low() {
+        let builder = TxBuilder {
+            outputs: vec![TxBuilderOutput {
+                sats: 1000,
+                address: Some("12tDncQvFZaZzqanupmtXpDUm42Wd4Cn4W".to_string()),
+                to: None,
+                script: None,
+                args: None,
+                encrypt_args: None,
+            }],
+            auto_fund: true,
+            fee_policy: FeePolicy::TotalFee(1),
+            utxos: Some(vec![utxo(0, 10000)]),
+            offline: true,
+            ..Default::default()
+        };
+
+        let wallet = Wallet::new(
+            "book fit fly ketchup also elevator scout mind edit fatal where rookie".to_string(),
+        );
+        let err = wallet.build_tx(&builder).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TwetchError>(),
+            Some(TwetchError::Fee(_))
+        ));
+
+        // signing a watch-only build later runs the same check
+        let watch_only = Wallet::watch_only(
+            wallet.account_public_key().unwrap(),
+            Some(wallet.wallet_xpub().unwrap()),
+        );
+        let unsigned = watch_only.build_tx(&builder).await.unwrap();
+        let err = wallet.sign_built_tx(&unsigned).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TwetchError>(),
+            Some(TwetchError::Fee(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn http() {
+        let api = GraphqlApi::new("http://127.0.0.1:1".to_string(), "token".to_string());
+        let err = api
+            .graphql("query { me }".to_string(), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            TwetchError::from_error(&err),
+            Some(TwetchError::Http { status: None, .. })
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+#[cfg(test)]
+mod fee_policy_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::json;
+    use twetch_sdk::{FeePolicy, FeeRates};
+
+    #[test]
+    fn fee_rates() {
+        let rates = FeeRates {
+            standard: 0.5,
+            data: 0.25,
+            total: None,
+        };
+        assert_eq!(rates.fee(200, 1000), 350);
+        assert_eq!(rates.fee(201, 0), 101);
+        assert_eq!(rates.min_rate(), 0.1);
+
+        let total = FeeRates {
+            total: Some(500),
+            ..rates
+        };
+        assert_eq!(total.fee(200, 1000), 500);
+    }
+
+    #[tokio::test]
+    async fn fixed_policies() -> Result<()> {
+        let rates = FeePolicy::rate(0.05).resolve().await?;
+        assert_eq!(rates.fee(1000, 0), 50);
+        assert_eq!(rates.min_rate(), 0.05);
+
+        let rates = FeePolicy::TotalFee(120).resolve().await?;
+        assert_eq!(rates.fee(1000, 1000), 120);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mapi_policy() -> Result<()> {
+        let url = serve(|request| async move {
+            assert_eq!(request.path, "/mapi/feeQuote");
+            assert_eq!(request.header("authorization").unwrap(), "Bearer token");
+
+            let payload = json!({
+                "apiVersion": "1.5.0",
+                "fees": [
+                    {
+                        "feeType": "standard",
+                        "miningFee": { "satoshis": 50, "bytes": 1000 },
+                        "relayFee": { "satoshis": 25, "bytes": 1000 },
+                    },
+                    {
+                        "feeType": "data",
+                        "miningFee": { "satoshis": 25, "bytes": 1000 },
+                        "relayFee": { "satoshis": 25, "bytes": 1000 },
+                    },
+                ],
+            });
+            StandInResponse::json(200, json!({ "payload": payload.to_string() }))
+        })
+        .await;
+
+        let policy = FeePolicy::Mapi {
+            url: format!("{}/mapi", url),
+            token: "token".to_string(),
+            miner_id: None,
+        };
+        let rates = policy.resolve().await?;
+
+        assert_eq!(rates.standard, 0.05);
+        assert_eq!(rates.data, 0.025);
+        assert_eq!(rates.fee(1000, 2000), 100);
+
+        Ok(())
+    }
+}
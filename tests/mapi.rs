@@ -0,0 +1,159 @@
+mod common;
+
+#[cfg(test)]
+mod mapi_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use bsv::PrivateKey;
+    use serde_json::{json, Value};
+    use twetch_sdk::{
+        BroadcastStatus, FeePolicy, MapiApi, MapiEnvelope, MapiSubmitOptions, TwetchError,
+    };
+
+    fn envelope(payload: Value) -> Value {
+        json!({
+            "payload": payload.to_string(),
+            "signature": null,
+            "publicKey": null,
+            "encoding": "UTF-8",
+            "mimetype": "application/json"
+        })
+    }
+
+    async fn miner() -> String {
+        serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+            match request.path.as_str() {
+                "/feeQuote" => StandInResponse::json(
+                    200,
+                    envelope(json!({
+                        "apiVersion": "1.4.0",
+                        "currentHighestBlockHeight": 800000,
+                        "fees": [{
+                            "feeType": "standard",
+                            "miningFee": { "satoshis": 1, "bytes": 20 },
+                            "relayFee": { "satoshis": 1, "bytes": 40 }
+                        }]
+                    })),
+                ),
+                "/tx/a?merkleProof=true&merkleFormat=TSC" => StandInResponse::json(
+                    200,
+                    envelope(json!({
+                        "txid": "a",
+                        "returnResult": "success",
+                        "resultDescription": "",
+                        "blockHeight": 800000,
+                        "confirmations": 2,
+                        "merkleProof": {
+                            "index": 1,
+                            "txOrId": "a",
+                            "target": "block",
+                            "nodes": ["b", "*"]
+                        }
+                    })),
+                ),
+                "/tx" => {
+                    assert_eq!(body["rawtx"], "0102");
+                    assert_eq!(body["callbackUrl"], "https://twetch.com/mapi");
+                    assert_eq!(body["merkleProof"], true);
+                    StandInResponse::json(
+                        200,
+                        envelope(json!({
+                            "txid": "a",
+                            "returnResult": "failure",
+                            "resultDescription": "Transaction already known",
+                            "conflictedWith": []
+                        })),
+                    )
+                }
+                "/tx/unauthorized" => StandInResponse::json(401, json!({ "error": "bad token" })),
+                _ => StandInResponse::json(404, json!({ "message": "not found" })),
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn fee_quote_and_status() -> Result<()> {
+        let api = MapiApi::new(miner().await, "token".to_string());
+
+        let quote = api.fee_quote().await?;
+        assert_eq!(quote.rate("standard"), Some(0.05));
+        assert_eq!(quote.current_highest_block_height, Some(800000));
+
+        let status = api.tx_status("a", true).await?;
+        assert_eq!(status.confirmations, Some(2));
+        assert_eq!(status.merkle_proof.unwrap().nodes, vec!["b", "*"]);
+
+        let err = api.tx_status("b", false).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TwetchError>(),
+            Some(&TwetchError::api("mapi", vec!["not found".to_string()]))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn submit_with_callbacks() -> Result<()> {
+        let api = MapiApi::new(miner().await, "token".to_string());
+        let options = MapiSubmitOptions {
+            callback_url: Some("https://twetch.com/mapi".to_string()),
+            merkle_proof: true,
+            ..Default::default()
+        };
+
+        let res = api.submit_tx(&vec![1, 2], &options).await?;
+        assert_eq!(res.return_result, "failure");
+        assert_eq!(res.status(), BroadcastStatus::AlreadyKnown);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requires_miner_signature() {
+        let url = miner().await;
+        let api = MapiApi::new(url.clone(), "token".to_string()).with_miner_id("02ab".to_string());
+        assert!(api.fee_quote().await.is_err());
+
+        let policy = FeePolicy::Mapi {
+            url,
+            token: "token".to_string(),
+            miner_id: Some("02ab".to_string()),
+        };
+        assert!(policy.resolve().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn http_status() {
+        let api = MapiApi::new(miner().await, "token".to_string());
+        let err = api.tx_status("unauthorized", false).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TwetchError>(),
+            Some(TwetchError::Http {
+                status: Some(401),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn verify_envelope() -> Result<()> {
+        let key = PrivateKey::from_random();
+        let payload = json!({ "txid": "a" }).to_string();
+        let signature = key.sign_message(payload.as_bytes())?;
+
+        let mut envelope = MapiEnvelope {
+            payload: Some(payload),
+            signature: Some(hex::encode(signature.to_der_bytes())),
+            public_key: Some(key.to_public_key()?.to_hex()?),
+            ..Default::default()
+        };
+        assert!(envelope.verify().is_ok());
+
+        envelope.payload = Some(json!({ "txid": "b" }).to_string());
+        assert!(envelope.verify().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+mod common;
+
+#[cfg(test)]
+mod rpc_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::{json, Value};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use twetch_sdk::{RPCCall, RPCClient, TwetchError};
+
+    fn answer(request: &Value) -> Value {
+        let id = request["id"].clone();
+        let result = match request["method"].as_str().unwrap() {
+            "getbestblockhash" => json!("00ab"),
+            "getrawmempool" => json!(["a", "b"]),
+            "getrawtransaction" => json!({
+                "txid": "a",
+                "hash": "a",
+                "version": 1,
+                "size": 85,
+                "locktime": 0,
+                "vin": [{
+                    "txid": "b",
+                    "vout": 0,
+                    "scriptSig": { "asm": "OP_TRUE", "hex": "51" },
+                    "sequence": 4294967295u32
+                }],
+                "vout": [{
+                    "value": 0.0,
+                    "n": 0,
+                    "scriptPubKey": { "asm": "OP_RETURN", "hex": "6a", "type": "nulldata" }
+                }],
+                "hex": "0100",
+                "confirmations": 3
+            }),
+            "gettxout" => Value::Null,
+            "testmempoolaccept" => json!([{
+                "txid": "a",
+                "allowed": false,
+                "reject-reason": "258: txn-mempool-conflict"
+            }]),
+            _ => {
+                return json!({
+                    "result": null,
+                    "id": id,
+                    "error": { "code": -32601, "message": "Method not found" }
+                })
+            }
+        };
+
+        json!({ "result": result, "id": id, "error": null })
+    }
+
+    /// Answers like a node, failed calls with a 500.
+    async fn node() -> (RPCClient, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+
+        let url = serve(move |request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let body: Value = serde_json::from_slice(&request.body).unwrap();
+                match body.as_array() {
+                    // answer batches backwards to check they are put back in order
+                    Some(calls) => StandInResponse::json(
+                        200,
+                        Value::Array(calls.iter().rev().map(answer).collect()),
+                    ),
+                    None => {
+                        let res = answer(&body);
+                        match res["error"].is_null() {
+                            true => StandInResponse::json(200, res),
+                            false => StandInResponse::json(500, res),
+                        }
+                    }
+                }
+            }
+        })
+        .await;
+
+        let client = serde_json::from_value(json!({
+            "host": url,
+            "user": "user",
+            "password": "password",
+        }))
+        .unwrap();
+
+        (client, hits)
+    }
+
+    #[tokio::test]
+    async fn typed_calls() -> Result<()> {
+        let (client, hits) = node().await;
+
+        let res = client.get_best_block_hash().await?;
+        assert_eq!(res.id, json!(1));
+        assert_eq!(res.into_result()?, "00ab");
+
+        let res = client.clone().get_raw_mempool().await?;
+        assert_eq!(res.id, json!(2));
+        assert_eq!(res.into_result()?, vec!["a", "b"]);
+
+        let tx = client
+            .get_raw_transaction_verbose("a")
+            .await?
+            .into_result()?;
+        assert_eq!(tx.vin[0].txid.as_deref(), Some("b"));
+        assert_eq!(tx.vout[0].script_pub_key.hex, "6a");
+        assert_eq!(tx.confirmations, Some(3));
+
+        let res = client.get_tx_out("a", 0, true).await?;
+        assert!(res.result.is_none() && res.error.is_none());
+
+        let res = client
+            .test_mempool_accept(&[vec![1]])
+            .await?
+            .into_result()?;
+        assert!(!res[0].allowed);
+        assert_eq!(
+            res[0].reject_reason.as_deref(),
+            Some("258: txn-mempool-conflict")
+        );
+
+        let err = client
+            .get_merkle_proof("a")
+            .await?
+            .into_result()
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TwetchError>(),
+            Some(&TwetchError::api(
+                "rpc",
+                vec!["-32601 Method not found".to_string()]
+            ))
+        );
+
+        // a failed call is the node's answer, not something to retry
+        assert_eq!(hits.load(Ordering::SeqCst), 6);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batch() -> Result<()> {
+        let (client, _) = node().await;
+
+        let res = client
+            .batch(&[
+                RPCCall::new("getbestblockhash", json!([])),
+                RPCCall::new("getrawmempool", json!([false])),
+                RPCCall::new("getmerkleproof", json!(["a"])),
+            ])
+            .await?;
+
+        let ids: Vec<Value> = res.iter().map(|e| e.id.clone()).collect();
+        assert_eq!(ids, vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(res[0].result, Some(json!("00ab")));
+        assert_eq!(res[1].result, Some(json!(["a", "b"])));
+        assert!(res[2].error.is_some());
+
+        let res = client.get_best_block_hash().await?;
+        assert_eq!(res.id, json!(4));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+#[cfg(test)]
+mod signer_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::json;
+    use std::sync::Arc;
+    use twetch_sdk::{MnemonicSigner, RemoteSigner, SignerRequest, TwetchError, Wallet};
+
+    const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
+
+    async fn stand_in_signer() -> String {
+        serve(|request| async move {
+            let signer = MnemonicSigner::new(SEED.to_string());
+            let request: SignerRequest = serde_json::from_slice(&request.body).unwrap();
+            let response = request.handle(&signer).await;
+            StandInResponse::json(200, serde_json::to_value(response).unwrap())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn remote_signer_keys() -> Result<()> {
+        let url = stand_in_signer().await;
+        let remote = Wallet::from_signer(Arc::new(RemoteSigner::connect(url, None).await?));
+        let local = Wallet::new(SEED.to_string());
+
+        assert_eq!(
+            remote.account_address()?.to_string()?,
+            local.account_address()?.to_string()?
+        );
+        assert_eq!(
+            remote.wallet_xpub()?.to_string()?,
+            local.wallet_xpub()?.to_string()?
+        );
+        assert!(remote.account_private_key().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remote_signer_sign_message() -> Result<()> {
+        let url = stand_in_signer().await;
+        let remote = Wallet::from_signer(Arc::new(RemoteSigner::connect(url, None).await?));
+        let local = Wallet::new(SEED.to_string());
+
+        assert_eq!(
+            remote.sign_message("twetch".to_string()).await?,
+            local.sign_message("twetch".to_string()).await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remote_signer_http_status() {
+        let url =
+            serve(
+                |_| async move { StandInResponse::json(401, json!({ "message": "unauthorized" })) },
+            )
+            .await;
+
+        let err = RemoteSigner::connect(url, Some("token".to_string()))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            TwetchError::from_error(&err),
+            Some(TwetchError::Http {
+                status: Some(401),
+                ..
+            })
+        ));
+    }
+}
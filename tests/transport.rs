@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod transport_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::json;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+    use twetch_sdk::{
+        HttpTransport, TransportConfig, TransportEvent, TransportLogger, UtxoDetectiveApi,
+    };
+
+    struct Events(Mutex<Vec<TransportEvent>>);
+
+    impl TransportLogger for Events {
+        fn log(&self, event: &TransportEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    fn transport() -> Result<HttpTransport> {
+        HttpTransport::new(TransportConfig {
+            backoff_ms: 1,
+            headers: vec![("x-client".to_string(), "tests".to_string())],
+            ..Default::default()
+        })
+    }
+
+    /// Fails the first two requests with a 503.
+    async fn flaky() -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+
+        let url = serve(move |request| {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                assert_eq!(request.header("x-client").unwrap(), "tests");
+                assert!(request
+                    .header("user-agent")
+                    .unwrap()
+                    .starts_with("twetch-sdk/"));
+
+                match attempt {
+                    0 | 1 => StandInResponse::json(503, json!({})),
+                    _ => StandInResponse::json(200, json!({ "satoshis": 1000 })),
+                }
+            }
+        })
+        .await;
+
+        (url, hits)
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests() -> Result<()> {
+        let (url, hits) = flaky().await;
+        let events = Arc::new(Events(Mutex::new(Vec::new())));
+
+        let api =
+            UtxoDetectiveApi::new(url).with_transport(transport()?.with_logger(events.clone()));
+        let balance = api.balance_by_address(&"address".to_string()).await?;
+
+        assert_eq!(balance.satoshis, 1000);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        let events = events.0.lock().unwrap();
+        let statuses: Vec<Option<u16>> = events.iter().map(|e| e.status).collect();
+        assert_eq!(statuses, vec![Some(503), Some(503), Some(200)]);
+        assert_eq!(events[2].attempt, 3);
+        assert_eq!(events[0].method, "GET");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sends_other_requests_once() -> Result<()> {
+        let (url, hits) = flaky().await;
+
+        let res = transport()?.post(format!("{}/sync/tx", url)).send().await?;
+        assert_eq!(res.status().as_u16(), 503);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        let res = transport()?
+            .post(format!("{}/sync/tx", url))
+            .idempotent()
+            .send()
+            .await?;
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn backoff() {
+        let config = TransportConfig {
+            backoff_ms: 100,
+            max_backoff_ms: 500,
+            ..Default::default()
+        };
+        let delays: Vec<u64> = (1..5).map(|e| config.backoff(e)).collect();
+        assert_eq!(delays, vec![100, 200, 400, 500]);
+    }
+}
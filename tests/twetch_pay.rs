@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod twetch_pay_tests {
     use anyhow::Result;
-    use twetch_sdk::ABIv1Schema;
+    use twetch_sdk::{ABIv1Schema, TwetchError, TwetchPay};
 
     #[test]
     fn schema() -> Result<()> {
@@ -22,4 +22,27 @@ mod twetch_pay_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn rejection() {
+        let rejected: anyhow::Error = TwetchError::api("publish", vec!["bad args".into()]).into();
+        assert!(TwetchPay::is_rejection(&rejected));
+
+        let rejected: anyhow::Error = TwetchError::Http {
+            url: Some("https://api.twetch.app".to_string()),
+            status: Some(401),
+            message: "unauthorized".to_string(),
+        }
+        .into();
+        assert!(TwetchPay::is_rejection(&rejected));
+
+        let unknown: anyhow::Error = TwetchError::Http {
+            url: Some("https://api.twetch.app".to_string()),
+            status: Some(502),
+            message: "bad gateway".to_string(),
+        }
+        .into();
+        assert!(!TwetchPay::is_rejection(&unknown));
+        assert!(!TwetchPay::is_rejection(&anyhow::anyhow!("timed out")));
+    }
 }
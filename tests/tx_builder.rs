@@ -0,0 +1,480 @@
+mod common;
+
+#[cfg(test)]
+mod tx_builder_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use bsv::{Script, Transaction, TxIn};
+    use serde_json::json;
+    use std::sync::Arc;
+    use twetch_sdk::{
+        plan_consolidation, plan_split, CoinSelection, DerivationPolicy, FeePolicy,
+        FileReservationStore, KeySigner, MemoryUtxoStore, Networks, Outpoint, Prevout, Recipient,
+        RecipientError, Reservation, ReservationStore, SdkConfig, StaticUtxoProvider, SweepSource,
+        TxBuilder, TxBuilderOutput, UtxoReservations, Wallet, WalletOutput, CHANGE_PATH_START,
+        UTXO,
+    };
+
+    const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
+    const TXID: &str = "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e";
+    const ADDRESS: &str = "12tDncQvFZaZzqanupmtXpDUm42Wd4Cn4W";
+
+    fn utxo(vout: u32, satoshis: u64) -> UTXO {
+        UTXO {
+            txid: TXID.to_string(),
+            vout,
+            satoshis,
+            path: -1,
+            script: None,
+            height: None,
+            script_type: None,
+        }
+    }
+
+    fn pay(sats: u64, to: &str) -> TxBuilderOutput {
+        TxBuilderOutput {
+            sats,
+            address: None,
+            to: Some(to.to_string()),
+            script: None,
+            args: None,
+            encrypt_args: None,
+        }
+    }
+
+    fn offline_builder(outputs: Vec<TxBuilderOutput>, utxos: Vec<UTXO>) -> TxBuilder {
+        TxBuilder {
+            outputs,
+            auto_fund: true,
+            coin_selection: CoinSelection::LargestFirst,
+            fee_policy: FeePolicy::rate(0.25),
+            utxos: Some(utxos),
+            offline: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn offline_build() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let builder = offline_builder(vec![pay(1000, ADDRESS)], vec![utxo(0, 10000)]);
+
+        let built = wallet.build_tx(&builder).await?;
+
+        // 226 bytes at 0.25 sats/byte
+        assert!(built.signed);
+        assert_eq!(built.fee_sats, 57);
+        assert_eq!(built.tx.get_ninputs(), 1);
+        assert_eq!(built.tx.get_noutputs(), 2);
+        assert_eq!(built.tx.get_output(0).unwrap().get_satoshis(), 1000);
+        assert_eq!(built.tx.get_output(1).unwrap().get_satoshis(), 8943);
+        assert_eq!(built.change_path, Some(-1));
+        assert_eq!(
+            built
+                .tx
+                .get_output(1)
+                .unwrap()
+                .get_script_pub_key()
+                .to_hex(),
+            wallet.account_locking_script()?.to_hex()
+        );
+
+        let tx_in = built.tx.get_input(0).unwrap();
+        assert!(!tx_in.get_unlocking_script().to_bytes().is_empty());
+
+        // same keys, same inputs and outputs, same transaction
+        let again = Wallet::new(SEED.to_string()).build_tx(&builder).await?;
+        assert_eq!(again.txid, built.txid);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn change_in_utxos() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string())
+            .with_utxo_provider(Arc::new(StaticUtxoProvider::new(vec![utxo(0, 10000)])))
+            .with_utxo_store(Arc::new(MemoryUtxoStore::new()));
+        let builder = offline_builder(vec![pay(1000, ADDRESS)], vec![utxo(0, 10000)]);
+
+        let built = wallet.build_tx(&builder).await?;
+        assert_eq!(built.change_path, Some(CHANGE_PATH_START));
+        wallet.record_tx(&built)?;
+
+        let utxos = wallet.utxos(&Networks::BSV, u64::MAX).await?;
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].outpoint(), Outpoint::new(&built.txid, 1));
+        assert_eq!(utxos[0].path, CHANGE_PATH_START);
+        assert_eq!(utxos[0].satoshis, 8943);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_build_keeps_change_index() -> Result<()> {
+        let wallet =
+            Wallet::new(SEED.to_string()).with_utxo_store(Arc::new(MemoryUtxoStore::new()));
+        let mut builder = offline_builder(vec![pay(1000, ADDRESS)], vec![utxo(0, 10000)]);
+        builder.fee_policy = FeePolicy::TotalFee(1);
+
+        assert!(wallet.build_tx(&builder).await.is_err());
+        assert_eq!(wallet.change_index(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn offline_prevouts() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+
+        let mut extended = Transaction::default();
+        extended.add_input(&TxIn::new(&hex::decode(TXID)?, 3, &Script::default(), None));
+        let prev_txid = extended.get_input(0).unwrap().get_prev_tx_id_hex(None);
+
+        let mut builder = offline_builder(vec![pay(1000, ADDRESS)], vec![utxo(0, 10000)]);
+        builder.extended_tx = Some(extended.to_compact_hex()?);
+
+        assert!(wallet.build_tx(&builder).await.is_err());
+
+        builder.prevouts = vec![Prevout {
+            txid: prev_txid,
+            vout: 3,
+            satoshis: 5000,
+        }];
+        let built = wallet.build_tx(&builder).await?;
+        assert_eq!(built.tx.get_ninputs(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn offline_requires_resolved_outputs() {
+        let wallet = Wallet::new(SEED.to_string());
+
+        let builder = offline_builder(vec![pay(1000, "1@twetch.me")], vec![utxo(0, 10000)]);
+        assert!(wallet.build_tx(&builder).await.is_err());
+
+        let mut builder = offline_builder(vec![pay(1000, ADDRESS)], vec![]);
+        builder.utxos = None;
+        assert!(wallet.build_tx(&builder).await.is_err());
+    }
+
+    #[test]
+    fn recipient_parse() {
+        assert_eq!(
+            Recipient::parse(ADDRESS),
+            Ok(Recipient::Address(ADDRESS.to_string()))
+        );
+        assert_eq!(
+            Recipient::parse("1@twetch.me"),
+            Ok(Recipient::Paymail("1@twetch.me".to_string()))
+        );
+        assert_eq!(
+            Recipient::parse("@1"),
+            Ok(Recipient::UserId("1".to_string()))
+        );
+        assert_eq!(
+            Recipient::parse("1"),
+            Ok(Recipient::UserId("1".to_string()))
+        );
+        assert_eq!(
+            Recipient::parse("$twetch"),
+            Ok(Recipient::Handle("twetch".to_string()))
+        );
+        assert_eq!(
+            Recipient::parse("1twetch"),
+            Ok(Recipient::OneHandle("twetch".to_string()))
+        );
+        assert_eq!(
+            Recipient::parse("1twetch").unwrap().polynym_query(),
+            "1twetch"
+        );
+        assert!(Recipient::parse("twetch").is_err());
+
+        // the last character of ADDRESS changed, breaking the checksum
+        let corrupted = "12tDncQvFZaZzqanupmtXpDUm42Wd4Cn4X";
+        assert_eq!(
+            Recipient::parse(corrupted).unwrap_err().recipient,
+            corrupted
+        );
+    }
+
+    #[tokio::test]
+    async fn unresolved_recipient() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let mut builder = offline_builder(
+            vec![pay(1000, ADDRESS), pay(500, "$twetch")],
+            vec![utxo(0, 10000)],
+        );
+
+        let err = wallet.build_tx(&builder).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RecipientError>().unwrap().recipient,
+            "$twetch"
+        );
+
+        builder.allow_partial_resolution = true;
+        let built = wallet.build_tx(&builder).await?;
+        assert_eq!(built.unresolved_recipients, vec!["$twetch".to_string()]);
+        assert_eq!(built.tx.get_noutputs(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sweep() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let builder = TxBuilder {
+            sweep_to: Some(ADDRESS.to_string()),
+            ..offline_builder(vec![], vec![utxo(0, 10000), utxo(1, 5000), utxo(2, 1)])
+        };
+
+        // the token output stays where it is
+        let built = wallet.build_tx(&builder).await?;
+
+        // 340 bytes at 0.25 sats/byte
+        assert_eq!(built.fee_sats, 85);
+        assert_eq!(built.total_cost_sats, 15000);
+        assert_eq!(built.tx.get_ninputs(), 2);
+        assert_eq!(built.tx.get_noutputs(), 1);
+        assert_eq!(built.tx.get_output(0).unwrap().get_satoshis(), 14915);
+        assert_eq!(built.change_path, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sweep_foreign_key() -> Result<()> {
+        let wif = Wallet::new(SEED.to_string())
+            .account_private_key()?
+            .to_wif()?;
+        let foreign = Wallet::from_signer(Arc::new(KeySigner::from_wif(&wif)?));
+
+        let builder = TxBuilder {
+            sweep_to: Some(ADDRESS.to_string()),
+            ..offline_builder(vec![], vec![utxo(0, 10000)])
+        };
+        let built = foreign.build_tx(&builder).await?;
+
+        let tx_in = built.tx.get_input(0).unwrap();
+        assert!(!tx_in.get_unlocking_script().to_bytes().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_sweep() -> Result<()> {
+        let url = serve(|_| async move {
+            StandInResponse::json(
+                200,
+                json!([{ "tx_hash": TXID, "tx_pos": 0, "value": 10000, "height": 800000 }]),
+            )
+        })
+        .await;
+
+        let wallet = Wallet::new(SEED.to_string()).with_config(SdkConfig::local(&url));
+        let wif = Wallet::new(SEED.to_string())
+            .account_private_key()?
+            .to_wif()?;
+
+        let built = wallet.sweep(&SweepSource::Wif(wif), &Networks::BSV).await?;
+        assert_eq!(
+            built.wallet_outputs,
+            vec![WalletOutput { vout: 0, path: -1 }]
+        );
+        assert!(wallet.reservations().is_reserved(&Outpoint::new(TXID, 0)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn coin_control_and_freezing() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let utxos = vec![utxo(0, 10000), utxo(1, 5000), utxo(2, 3000)];
+        wallet.freeze(Outpoint::new(TXID, 0));
+
+        let builder = offline_builder(vec![pay(1000, ADDRESS)], utxos.clone());
+        let built = wallet.build_tx(&builder).await?;
+        assert_eq!(built.utxos.len(), 1);
+        assert_eq!(built.utxos[0].as_ref().unwrap().vout, 1);
+
+        let mut builder = offline_builder(vec![pay(1000, ADDRESS)], utxos.clone());
+        builder.coin_control = vec![Outpoint::new(TXID, 2), Outpoint::new(TXID, 0)];
+        let built = wallet.build_tx(&builder).await?;
+        let vouts: Vec<u32> = built
+            .utxos
+            .iter()
+            .map(|e| e.as_ref().unwrap().vout)
+            .collect();
+        assert_eq!(vouts, vec![2, 0]);
+
+        builder.coin_control = vec![Outpoint::new(TXID, 7)];
+        assert!(wallet.build_tx(&builder).await.is_err());
+
+        // coin control funds on its own, pricing the change it adds
+        let mut builder = offline_builder(vec![pay(1000, ADDRESS)], utxos.clone());
+        builder.auto_fund = false;
+        builder.coin_control = vec![Outpoint::new(TXID, 1)];
+        let built = wallet.build_tx(&builder).await?;
+        assert_eq!(built.fee_sats, 57);
+        assert_eq!(built.tx.get_noutputs(), 2);
+        assert_eq!(built.tx.get_output(1).unwrap().get_satoshis(), 3943);
+
+        // the freeze list carries over to other derivation policies
+        let other = wallet.with_derivation_policy(DerivationPolicy::new(&Networks::BSV, 1))?;
+        assert!(other.is_frozen(&Outpoint::new(TXID, 0)));
+
+        wallet.unfreeze(&Outpoint::new(TXID, 0));
+        assert!(wallet.frozen().is_empty());
+        assert!(other.frozen().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reservations() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let builder = offline_builder(
+            vec![pay(1000, ADDRESS)],
+            vec![utxo(0, 10000), utxo(1, 5000)],
+        );
+
+        // a second build while the first is in flight picks the other utxo
+        let first = wallet.build_tx(&builder).await?;
+        let second = wallet.build_tx(&builder).await?;
+        assert_eq!(first.utxos[0].as_ref().unwrap().vout, 0);
+        assert_eq!(second.utxos[0].as_ref().unwrap().vout, 1);
+        assert!(wallet.build_tx(&builder).await.is_err());
+
+        let reservations = wallet.reservations();
+        reservations.release(&first.reservation.unwrap())?;
+        reservations.commit(&second.reservation.unwrap())?;
+        assert!(!reservations.is_reserved(&Outpoint::new(TXID, 0)));
+        assert!(reservations.is_reserved(&Outpoint::new(TXID, 1)));
+
+        // a failed build doesn't hold on to its selection
+        let mut broke = offline_builder(vec![pay(9990, ADDRESS)], vec![utxo(0, 10000)]);
+        broke.coin_control = vec![Outpoint::new(TXID, 0)];
+        assert!(wallet.build_tx(&broke).await.is_err());
+        assert!(!reservations.is_reserved(&Outpoint::new(TXID, 0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reservation_store() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("reservations-{}.json", std::process::id()));
+        let outpoints = vec![Outpoint::new(TXID, 0), Outpoint::new(TXID, 1)];
+
+        let reservations =
+            UtxoReservations::with_store(60000, Box::new(FileReservationStore::new(path.clone())))?;
+        let id = reservations.reserve(&outpoints)?;
+        assert!(reservations.reserve(&outpoints[1..]).is_err());
+
+        let restored =
+            UtxoReservations::with_store(60000, Box::new(FileReservationStore::new(path.clone())))?;
+        assert!(restored.is_reserved(&outpoints[0]));
+
+        restored.release(&id)?;
+        let restored =
+            UtxoReservations::with_store(60000, Box::new(FileReservationStore::new(path.clone())))?;
+        assert!(restored.reservations().is_empty());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    struct FailingStore;
+
+    impl ReservationStore for FailingStore {
+        fn load(&self) -> Result<Vec<Reservation>> {
+            Ok(Vec::new())
+        }
+
+        fn save(&self, _: &Vec<Reservation>) -> Result<()> {
+            anyhow::bail!("disk full")
+        }
+    }
+
+    #[test]
+    fn reservation_store_failure() -> Result<()> {
+        let outpoints = vec![Outpoint::new(TXID, 0)];
+
+        let reservations = UtxoReservations::with_store(60000, Box::new(FailingStore))?;
+        assert!(reservations.reserve(&outpoints).is_err());
+        assert!(!reservations.is_reserved(&outpoints[0]));
+        assert!(reservations.reservations().is_empty());
+
+        let reservations = UtxoReservations::new(60000);
+        let first = reservations.reserve(&outpoints)?;
+        reservations.release(&first)?;
+        let second = reservations.reserve(&outpoints)?;
+        assert_ne!(first, second);
+        assert_eq!(second.len(), 32);
+
+        Ok(())
+    }
+
+    #[test]
+    fn split_plan() {
+        assert_eq!(plan_split(1000, 3, 0).unwrap(), vec![333, 333, 333]);
+        assert_eq!(plan_split(1100, 3, 100).unwrap(), vec![333, 333, 333]);
+        assert!(plan_split(1000, 1, 0).is_err());
+        assert!(plan_split(1000, 6, 0).is_err());
+        assert!(plan_split(1000, 4, 250).is_err());
+    }
+
+    #[tokio::test]
+    async fn consolidate_and_split() -> Result<()> {
+        let mut utxos: Vec<UTXO> = (0..10).map(|i| utxo(i, 1000 + i as u64)).collect();
+        utxos.push(utxo(10, 1));
+
+        // 148 byte inputs, 4 fit in 700 bytes with the output and overhead
+        let batches = plan_consolidation(&utxos, 700)?;
+        let sizes: Vec<usize> = batches.iter().map(|e| e.len()).collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+        assert_eq!(batches[0][0].vout, 0);
+
+        let wallet = Wallet::new(SEED.to_string())
+            .with_utxo_provider(Arc::new(StaticUtxoProvider::new(utxos)));
+        let built = wallet
+            .consolidate(&Networks::BSV, 700, &FeePolicy::rate(0.25))
+            .await?;
+        assert_eq!(built.len(), 3);
+        assert_eq!(built[0].tx.get_ninputs(), 4);
+        assert_eq!(built[0].tx.get_noutputs(), 1);
+
+        // consolidation transactions hold on to their utxos until released
+        for e in &built {
+            wallet
+                .reservations()
+                .release(e.reservation.as_ref().unwrap())?;
+        }
+
+        let built = wallet
+            .split(
+                &Outpoint::new(TXID, 9),
+                4,
+                &Networks::BSV,
+                &FeePolicy::rate(0.25),
+            )
+            .await?;
+        // 328 bytes at 0.25 sats/byte, the 3 sats that don't divide go to the miner
+        assert_eq!(built.tx.get_noutputs(), 4);
+        for i in 0..4 {
+            assert_eq!(built.tx.get_output(i).unwrap().get_satoshis(), 231);
+        }
+        assert_eq!(built.fee_sats, 85);
+        assert_eq!(built.wallet_outputs[2], WalletOutput { vout: 2, path: -1 });
+
+        // the second batch can't be built, the first lets go of its utxos
+        let taken = wallet.reservations().reserve(&[Outpoint::new(TXID, 5)])?;
+        assert!(wallet
+            .consolidate(&Networks::BSV, 700, &FeePolicy::rate(0.25))
+            .await
+            .is_err());
+        assert!(!wallet.reservations().is_reserved(&Outpoint::new(TXID, 0)));
+        wallet.reservations().release(&taken)?;
+
+        Ok(())
+    }
+}
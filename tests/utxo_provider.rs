@@ -0,0 +1,143 @@
+mod common;
+
+#[cfg(test)]
+mod utxo_provider_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::json;
+    use std::sync::Arc;
+    use twetch_sdk::{
+        Balance, CoinSelection, CompositeProvider, FeePolicy, Networks, Outpoint, SdkConfig,
+        StaticUtxoProvider, TxBuilder, TxBuilderOutput, UtxoProvider, UtxoQuery, Wallet, UTXO,
+    };
+
+    const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
+    const TXID: &str = "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e";
+    const ADDRESS: &str = "12tDncQvFZaZzqanupmtXpDUm42Wd4Cn4W";
+
+    fn utxo(vout: u32, satoshis: u64, height: Option<i64>) -> UTXO {
+        UTXO {
+            txid: TXID.to_string(),
+            vout,
+            satoshis,
+            path: -1,
+            script: None,
+            height,
+            script_type: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn composite() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let query = UtxoQuery {
+            account_public_key: wallet.account_public_key()?,
+            network: Networks::BSV,
+            amount: 0,
+        };
+
+        let provider = CompositeProvider::new(vec![
+            Arc::new(StaticUtxoProvider::new(vec![
+                utxo(0, 1000, None),
+                utxo(1, 2000, None),
+            ])),
+            Arc::new(StaticUtxoProvider::new(vec![
+                utxo(1, 2000, Some(700000)),
+                utxo(2, 3000, None),
+            ])),
+        ]);
+        let utxos = provider.utxos(&query).await?;
+
+        let vouts: Vec<u32> = utxos.iter().map(|e| e.vout).collect();
+        assert_eq!(vouts, vec![0, 1, 2]);
+        assert_eq!(utxos[1].height, Some(700000));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_provider() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string()).with_utxo_provider(Arc::new(
+            StaticUtxoProvider::new(vec![utxo(0, 10000, None)]),
+        ));
+        assert_eq!(wallet.utxos(&Networks::BSV, 0).await?.len(), 1);
+
+        let builder = TxBuilder {
+            outputs: vec![TxBuilderOutput {
+                sats: 1000,
+                address: None,
+                to: Some(ADDRESS.to_string()),
+                script: None,
+                args: None,
+                encrypt_args: None,
+            }],
+            auto_fund: true,
+            coin_selection: CoinSelection::LargestFirst,
+            fee_policy: FeePolicy::rate(0.25),
+            ..Default::default()
+        };
+        let built = wallet.build_tx(&builder).await?;
+        assert_eq!(built.utxos[0].as_ref().unwrap().txid, TXID);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn balance() -> Result<()> {
+        let mut paymail = utxo(3, 4000, None);
+        paymail.path = 7;
+
+        // the account address, one utxo the provider already returned, and
+        // the address of path 7 on utxo detective for its height
+        let url = serve(|request| async move {
+            if request.path.starts_with("/utxos/") {
+                return StandInResponse::json(
+                    200,
+                    json!([{ "txid": TXID, "vout": 3, "satoshis": "4000", "block_height": 700001 }]),
+                );
+            }
+
+            StandInResponse::json(
+                200,
+                json!([
+                    { "tx_hash": TXID, "tx_pos": 0, "value": 10000, "height": 700000 },
+                    { "tx_hash": TXID, "tx_pos": 4, "value": 500, "height": 0 },
+                ]),
+            )
+        })
+        .await;
+
+        let wallet = Wallet::new(SEED.to_string())
+            .with_config(SdkConfig::local(&url))
+            .with_utxo_provider(Arc::new(StaticUtxoProvider::new(vec![
+                utxo(0, 10000, Some(700000)),
+                utxo(1, 1, Some(700000)),
+                utxo(2, 2000, None),
+                paymail,
+            ])));
+        wallet.freeze(Outpoint::new(TXID, 2));
+
+        let balance = wallet.balance(&Networks::BSV).await?;
+        assert_eq!(balance.confirmed, 14001);
+        assert_eq!(balance.unconfirmed, 2500);
+        assert_eq!(balance.spendable, 14500);
+        assert_eq!(balance.frozen, 2000);
+        assert_eq!(balance.token_outputs, 1);
+        assert_eq!(balance.paths.get(&-1), Some(&12501));
+        assert_eq!(balance.paths.get(&7), Some(&4000));
+        assert_eq!(balance.total(), 16501);
+
+        // builds don't fund from token outputs or the account address
+        let vouts: Vec<u32> = wallet
+            .utxos(&Networks::BSV, 0)
+            .await?
+            .iter()
+            .map(|e| e.vout)
+            .collect();
+        assert_eq!(vouts, vec![0, 3]);
+
+        assert_eq!(Balance::from_utxos(&[], |_| false), Balance::default());
+
+        Ok(())
+    }
+}
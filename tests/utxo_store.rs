@@ -0,0 +1,179 @@
+mod common;
+
+#[cfg(test)]
+mod utxo_store_tests {
+    use crate::common::{serve, StandInResponse};
+    use anyhow::Result;
+    use serde_json::{json, Value};
+    use twetch_sdk::{
+        reconcile_utxo_store, FileUtxoStore, MemoryUtxoStore, Outpoint, StoredUtxo,
+        UtxoDetectiveApi, UtxoStore, UTXO,
+    };
+
+    const TXID: &str = "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e";
+    const SPEND: &str = "1b2c3d4e8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a";
+
+    fn utxo(vout: u32, satoshis: u64) -> UTXO {
+        UTXO {
+            txid: TXID.to_string(),
+            vout,
+            satoshis,
+            path: -1,
+            script: None,
+            height: None,
+            script_type: None,
+        }
+    }
+
+    fn unspent(vout: u32, satoshis: u64) -> StoredUtxo {
+        StoredUtxo {
+            utxo: utxo(vout, satoshis),
+            confirmed: true,
+            spent: false,
+            spent_by: None,
+            spent_at: None,
+        }
+    }
+
+    fn vouts(utxos: &[UTXO]) -> Vec<u32> {
+        let mut vouts: Vec<u32> = utxos.iter().map(|e| e.vout).collect();
+        vouts.sort();
+        vouts
+    }
+
+    #[test]
+    fn spend_tracking() -> Result<()> {
+        let store = MemoryUtxoStore::new();
+        store.put(vec![unspent(0, 1000), unspent(1, 2000)])?;
+
+        store.mark_spent(&[utxo(0, 1000), utxo(5, 500)], Some(SPEND))?;
+        assert_eq!(vouts(&store.unspent()?), vec![1]);
+        assert_eq!(
+            store.get(&Outpoint::new(TXID, 5))?.unwrap().spent_by,
+            Some(SPEND.to_string())
+        );
+
+        // spent ones are hidden even while the remote still lists them
+        let merged = store.merge(vec![utxo(0, 1000), utxo(2, 3000)])?;
+        assert_eq!(vouts(&merged), vec![1, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_store() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("utxos-{}.json", std::process::id()));
+
+        let store = FileUtxoStore::new(path.clone())?;
+        store.put(vec![unspent(0, 1000), unspent(1, 2000)])?;
+        store.remove(&[Outpoint::new(TXID, 1)])?;
+
+        let restored = FileUtxoStore::new(path.clone())?;
+        assert_eq!(vouts(&restored.unspent()?), vec![0]);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reconcile() -> Result<()> {
+        let url = serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            let outpoints = body["outpoints"].as_array().map(|e| e.len()).unwrap_or(0);
+
+            match request.path.as_str() {
+                "/spends/values" => {
+                    assert_eq!(outpoints, 3);
+                    let outpoints: Vec<Value> = body["outpoints"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|e| match &e.as_str().unwrap()[64..] {
+                            "01000000" => json!({ "h": 0, "o": SPEND }),
+                            "02000000" => json!({ "h": 800000, "o": SPEND }),
+                            _ => Value::Null,
+                        })
+                        .collect();
+                    StandInResponse::json(200, json!({ "outpoints": outpoints }))
+                }
+                "/mempool" => {
+                    assert_eq!(outpoints, 1);
+                    assert_eq!(body["address"], "address");
+                    StandInResponse::json(
+                        200,
+                        json!({
+                            "spent": [false],
+                            "new_utxos": [
+                                { "txid": SPEND, "vout": 0, "satoshis": "700", "block_height": null },
+                            ],
+                        }),
+                    )
+                }
+                _ => StandInResponse::json(404, json!({})),
+            }
+        })
+        .await;
+
+        let store = MemoryUtxoStore::new();
+        store.put(vec![unspent(0, 1000), unspent(1, 2000)])?;
+        store.mark_spent(&[utxo(2, 3000)], Some(SPEND))?;
+
+        let api = UtxoDetectiveApi::new(url);
+        reconcile_utxo_store(&store, &api, Some("address".to_string())).await?;
+
+        // spend of vout 2 was mined, vout 1 is spent in the mempool
+        assert!(store.get(&Outpoint::new(TXID, 2))?.is_none());
+        let spent = store.get(&Outpoint::new(TXID, 1))?.unwrap();
+        assert!(spent.spent);
+        assert_eq!(spent.spent_by, Some(SPEND.to_string()));
+
+        let new = store.get(&Outpoint::new(SPEND, 0))?.unwrap();
+        assert!(!new.confirmed);
+        assert_eq!(new.utxo.satoshis, 700);
+        assert_eq!(store.unspent()?.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reconcile_unseen_spend() -> Result<()> {
+        let url = serve(|request| async move {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            let outpoints = body["outpoints"].as_array().map(|e| e.len()).unwrap_or(0);
+
+            match request.path.as_str() {
+                "/spends/values" => {
+                    StandInResponse::json(200, json!({ "outpoints": vec![Value::Null; outpoints] }))
+                }
+                "/mempool" => {
+                    // only the spend that timed out is checked
+                    assert_eq!(outpoints, 1);
+                    StandInResponse::json(200, json!({ "spent": [false], "new_utxos": [] }))
+                }
+                _ => StandInResponse::json(404, json!({})),
+            }
+        })
+        .await;
+
+        let store = MemoryUtxoStore::new();
+        store.put(vec![StoredUtxo {
+            spent: true,
+            spent_by: Some(SPEND.to_string()),
+            spent_at: Some(0),
+            ..unspent(0, 1000)
+        }])?;
+        store.mark_spent(&[utxo(1, 2000)], Some(SPEND))?;
+
+        let api = UtxoDetectiveApi::new(url);
+        reconcile_utxo_store(&store, &api, None).await?;
+
+        // the old spend never showed up, the recent one may still
+        let dropped = store.get(&Outpoint::new(TXID, 0))?.unwrap();
+        assert!(!dropped.spent);
+        assert_eq!(dropped.spent_by, None);
+        assert!(store.get(&Outpoint::new(TXID, 1))?.unwrap().spent);
+        assert_eq!(vouts(&store.unspent()?), vec![0]);
+
+        Ok(())
+    }
+}
@@ -1,8 +1,13 @@
 #[cfg(test)]
 mod wallet_tests {
     use anyhow::Result;
-    use bsv::{ExtendedPublicKey, Script, TxOut};
-    use twetch_sdk::{Networks, TxBuilder, TxBuilderOutput, Wallet};
+    use bsv::{ExtendedPublicKey, Script, Transaction, TxOut};
+    use std::sync::Arc;
+    use twetch_sdk::{
+        CoinSelection, DerivationPolicy, FeePolicy, InputScriptType, MemoryUtxoStore,
+        MnemonicError, Networks, StoredUtxo, TxBuilder, TxBuilderOutput, UtxoStore, Wallet,
+        CHANGE_PATH_START, UTXO,
+    };
 
     const SEED: &str = "book fit fly ketchup also elevator scout mind edit fatal where rookie";
 
@@ -72,6 +77,107 @@ mod wallet_tests {
         Ok(())
     }
 
+    #[test]
+    fn estimate_size() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let mut tx = Transaction::default();
+        tx.add_output(&TxOut::new(1000, &wallet.account_locking_script()?));
+
+        let utxo = UTXO {
+            txid: "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e".to_string(),
+            vout: 0,
+            satoshis: 1000,
+            path: -1,
+            script: None,
+            height: None,
+            script_type: None,
+        };
+        let custom = UTXO {
+            script_type: Some(InputScriptType::Custom {
+                unlocking_script_size: 200,
+            }),
+            ..utxo.clone()
+        };
+
+        assert_eq!(
+            InputScriptType::from_locking_script(&wallet.account_locking_script()?),
+            InputScriptType::P2PKH
+        );
+        assert_eq!(
+            TxBuilder::input_script_type(None, Some(&utxo)),
+            InputScriptType::P2PKH
+        );
+        assert_eq!(
+            TxBuilder::input_script_type(None, None),
+            InputScriptType::Sigil
+        );
+
+        // version, input count, p2pkh, sigil and custom inputs, output count, output, change, locktime
+        let utxos = vec![Some(utxo), None, Some(custom)];
+        assert_eq!(
+            TxBuilder::estimate_size(&tx, &utxos, 34)?,
+            4 + 1 + 148 + 181 + 241 + 1 + 34 + 34 + 4
+        );
+        assert_eq!(
+            TxBuilder::estimate_size(&tx, &utxos, 0)?,
+            4 + 1 + 148 + 181 + 241 + 1 + 34 + 4
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn estimate_signed_size() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let watch_only =
+            Wallet::watch_only(wallet.account_public_key()?, Some(wallet.wallet_xpub()?));
+
+        let utxos: Vec<UTXO> = (0..3)
+            .map(|vout| UTXO {
+                txid: "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e"
+                    .to_string(),
+                vout,
+                satoshis: 1000,
+                path: -1,
+                script: None,
+                height: None,
+                script_type: None,
+            })
+            .collect();
+
+        let builder = TxBuilder {
+            outputs: vec![TxBuilderOutput {
+                sats: 2500,
+                address: Some(wallet.account_address()?.to_string()?),
+                to: None,
+                script: None,
+                args: None,
+                encrypt_args: None,
+            }],
+            auto_fund: true,
+            coin_selection: CoinSelection::LargestFirst,
+            fee_policy: FeePolicy::rate(0.5),
+            utxos: Some(utxos),
+            offline: true,
+            ..Default::default()
+        };
+
+        let unsigned = watch_only.build_tx(&builder).await?;
+        let estimate = TxBuilder::estimate_size(&unsigned.tx, &unsigned.utxos, 0)?;
+
+        let signed = wallet.sign_built_tx(&unsigned).await?;
+        let size = signed.tx.get_size()?;
+
+        // signatures are 71 or 72 bytes, the estimate assumes 72
+        let ninputs = signed.tx.get_ninputs();
+        assert_eq!(ninputs, 3);
+        assert!(size <= estimate && size + ninputs >= estimate);
+        assert!(signed.fee_sats >= (size as f64 * 0.5).ceil() as u64);
+        assert!(signed.fee_rate >= 0.5);
+
+        Ok(())
+    }
+
     #[test]
     fn xpub_derivation() -> Result<()> {
         let wallet = Wallet::new(SEED.to_string());
@@ -105,6 +211,232 @@ mod wallet_tests {
         Ok(())
     }
 
+    #[test]
+    fn from_mnemonic() -> Result<()> {
+        let wallet = Wallet::from_mnemonic(&format!("  {}  ", SEED.to_uppercase()), None)?;
+        assert_eq!(
+            wallet.xpub()?.to_string()?,
+            Wallet::new(SEED.to_string()).xpub()?.to_string()?
+        );
+
+        let protected = Wallet::from_mnemonic(SEED, Some("twetch".to_string()))?;
+        assert_ne!(protected.xpub()?.to_string()?, wallet.xpub()?.to_string()?);
+        Ok(())
+    }
+
+    #[test]
+    fn validate_mnemonic() -> Result<()> {
+        assert_eq!(
+            Wallet::validate_mnemonic(
+                "book fit fly ketchup also elevator scout mind edit fatal where"
+            ),
+            Err(MnemonicError::WordCount(11))
+        );
+        assert_eq!(
+            Wallet::validate_mnemonic(
+                "book fit fly ketchup also elevator scout mind edit fatal where rookiee"
+            ),
+            Err(MnemonicError::UnknownWord {
+                index: 11,
+                word: "rookiee".to_string()
+            })
+        );
+        assert_eq!(
+            Wallet::validate_mnemonic(
+                "book fit fly ketchup also elevator scout mind edit fatal where where"
+            ),
+            Err(MnemonicError::Checksum)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn generate_mnemonic() -> Result<()> {
+        for word_count in [12, 24] {
+            let phrase = Wallet::generate_mnemonic(word_count)?;
+            assert_eq!(phrase.split(' ').count(), word_count);
+            assert!(Wallet::from_mnemonic(&phrase, None).is_ok());
+        }
+
+        assert!(Wallet::generate_mnemonic(13).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn derivation_policy() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        assert_eq!(wallet.derivation_policy(), DerivationPolicy::default());
+
+        let mainnet = wallet.with_derivation_policy(DerivationPolicy::new(&Networks::BSV, 0))?;
+        assert_eq!(
+            mainnet.wallet_xpub()?.to_string()?,
+            wallet.wallet_xpub()?.to_string()?
+        );
+        assert_eq!(
+            mainnet.account_address()?.to_string()?,
+            wallet.account_address()?.to_string()?
+        );
+
+        let testnet = wallet.with_derivation_policy(DerivationPolicy::new(&Networks::TBSV, 0))?;
+        assert_eq!(
+            testnet.wallet_xpub()?.get_public_key().to_hex()?,
+            wallet
+                .xpriv()?
+                .derive_from_path("m/44'/1'/0'/0")?
+                .get_public_key()
+                .to_hex()?
+        );
+
+        let second = wallet.with_derivation_policy(DerivationPolicy::new(&Networks::BSV, 1))?;
+        assert_eq!(
+            second.account_public_key()?.to_hex()?,
+            wallet
+                .xpub()?
+                .derive_from_path("m/0/1")?
+                .get_public_key()
+                .to_hex()?
+        );
+
+        let legacy = wallet.with_derivation_policy(DerivationPolicy::custom(
+            "m/0/0".to_string(),
+            "m/0'/0".to_string(),
+            "m/0'/1".to_string(),
+        ))?;
+        assert_eq!(
+            legacy.wallet_xpub()?.get_public_key().to_hex()?,
+            wallet
+                .xpriv()?
+                .derive_from_path("m/0'/0")?
+                .get_public_key()
+                .to_hex()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn change_script() -> Result<()> {
+        // without a store to record it, change goes to the account address
+        let wallet = Wallet::new(SEED.to_string());
+        let (script, path) = wallet.next_change_script()?;
+        assert_eq!(path, -1);
+        assert_eq!(script.to_hex(), wallet.account_locking_script()?.to_hex());
+        assert_eq!(wallet.change_index(), 0);
+
+        let wallet = wallet.with_utxo_store(Arc::new(MemoryUtxoStore::new()));
+        wallet.set_change_index(5);
+
+        let (script, path) = wallet.next_change_script()?;
+        assert_eq!(path, CHANGE_PATH_START + 5);
+        assert_eq!(wallet.change_index(), 6);
+        assert_eq!(
+            script.to_hex(),
+            wallet
+                .wallet_xpriv()?
+                .derive(CHANGE_PATH_START as u32 + 5)?
+                .get_private_key()
+                .to_public_key()?
+                .to_p2pkh_address()?
+                .get_locking_script()?
+                .to_hex()
+        );
+
+        let (_, path) = wallet.next_change_script()?;
+        assert_eq!(path, CHANGE_PATH_START + 6);
+
+        // a failed build hands its change key back
+        wallet.release_change_path(CHANGE_PATH_START + 6);
+        assert_eq!(wallet.change_index(), 6);
+        wallet.release_change_path(CHANGE_PATH_START + 3);
+        assert_eq!(wallet.change_index(), 6);
+
+        let watch_only = Wallet::watch_only(wallet.account_public_key()?, None);
+        let (script, path) = watch_only.next_change_script()?;
+        assert_eq!(path, -1);
+        assert_eq!(script.to_hex(), wallet.account_locking_script()?.to_hex());
+
+        Ok(())
+    }
+
+    #[test]
+    fn change_index_from_utxos() -> Result<()> {
+        let utxo = |path: i32| UTXO {
+            txid: "8e8b8fd3b0dfc0c8a2a9ba0b1e0d4c2f5b7c6e1f3a4d5b6c7d8e9f0a1b2c3d4e".to_string(),
+            vout: 0,
+            satoshis: 1000,
+            path,
+            script: None,
+            height: None,
+            script_type: None,
+        };
+
+        // paymail paths and account utxos don't move the change index
+        let wallet = Wallet::new(SEED.to_string());
+        wallet.observe_change_paths(&[utxo(-1), utxo(40)]);
+        assert_eq!(wallet.change_index(), 0);
+
+        wallet.observe_change_paths(&[utxo(CHANGE_PATH_START + 7), utxo(CHANGE_PATH_START + 2)]);
+        assert_eq!(wallet.change_index(), 8);
+        wallet.observe_change_paths(&[utxo(CHANGE_PATH_START + 3)]);
+        assert_eq!(wallet.change_index(), 8);
+
+        // a restarted wallet picks up where the stored change left off
+        let store = Arc::new(MemoryUtxoStore::new());
+        store.put(vec![StoredUtxo {
+            utxo: utxo(CHANGE_PATH_START + 4),
+            confirmed: false,
+            spent: true,
+            spent_by: None,
+            spent_at: None,
+        }])?;
+        let restarted = Wallet::new(SEED.to_string()).with_utxo_store(store);
+        let (_, path) = restarted.next_change_address()?;
+        assert_eq!(path, CHANGE_PATH_START + 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_only() -> Result<()> {
+        let wallet = Wallet::new(SEED.to_string());
+        let watch_only =
+            Wallet::watch_only(wallet.account_public_key()?, Some(wallet.wallet_xpub()?));
+
+        assert!(watch_only.is_watch_only());
+        assert_eq!(
+            watch_only.account_address()?.to_string()?,
+            wallet.account_address()?.to_string()?
+        );
+        assert_eq!(
+            watch_only.display_address(&Networks::BSV)?,
+            wallet.display_address(&Networks::BSV)?
+        );
+        assert!(watch_only.account_private_key().is_err());
+        assert!(watch_only.sign_message("twetch".to_string()).await.is_err());
+
+        let builder = TxBuilder {
+            outputs: vec![TxBuilderOutput {
+                sats: 0,
+                address: None,
+                to: None,
+                script: None,
+                args: Some(vec!["twetch".to_string()]),
+                encrypt_args: None,
+            }],
+            ..Default::default()
+        };
+
+        let unsigned = watch_only.build_tx(&builder).await?;
+        assert!(!unsigned.signed);
+
+        let signed = wallet.sign_built_tx(&unsigned).await?;
+        assert!(signed.signed);
+        assert_eq!(signed.tx.get_noutputs(), 1);
+        assert!(wallet.sign_built_tx(&signed).await.is_err());
+
+        Ok(())
+    }
+
     //#[test]
     //fn segwit_address() -> Result<()> {
     //let wallet = Wallet::new(SEED.to_string());